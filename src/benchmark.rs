@@ -0,0 +1,502 @@
+//! Guided benchmark: sample through a series of prompted phases and save the
+//! per-phase rates to a file that can be compared across machines.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::compare::{format_change, format_ratio, source_key};
+use crate::discovery::{self, I2cTopology};
+use crate::rates::{Interval, RateTracker, Totals};
+use crate::roots::FsRoots;
+use crate::sampler::Backend;
+use crate::schedule::Schedule;
+
+/// Format identifier of benchmark files.
+pub const FORMAT_NAME: &str = "i2c-int-monitor-benchmark";
+
+/// Current benchmark file format version.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// One prompted phase of a benchmark.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Phase {
+    pub name: String,
+    pub duration_s: f64,
+    /// What the tester is asked to do
+    pub prompt: String,
+}
+
+impl Phase {
+    fn new(name: &str, duration_s: f64, prompt: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            duration_s,
+            prompt: prompt.to_string(),
+        }
+    }
+
+    /// Phases used when none are given: idle, touchpad swipe and typing.
+    pub fn defaults() -> Vec<Self> {
+        vec![
+            Self::new("idle", 10.0, "Don't touch the touchpad or keyboard"),
+            Self::new("touchpad", 10.0, "Swipe continuously on the touchpad"),
+            Self::new("typing", 10.0, "Type continuously on the keyboard"),
+        ]
+    }
+}
+
+impl FromStr for Phase {
+    type Err = anyhow::Error;
+
+    /// Parse `NAME:SECS[:PROMPT]`.
+    fn from_str(spec: &str) -> Result<Self> {
+        let mut parts = spec.splitn(3, ':');
+        let name = parts.next().unwrap_or_default().trim();
+        let duration = parts
+            .next()
+            .with_context(|| format!("expected NAME:SECS[:PROMPT], got {:?}", spec))?;
+        let duration_s: f64 = duration
+            .trim()
+            .parse()
+            .with_context(|| format!("invalid duration {:?} in {:?}", duration, spec))?;
+        if name.is_empty() || duration_s.is_nan() || duration_s <= 0.0 {
+            bail!(
+                "expected NAME:SECS[:PROMPT] with a positive SECS, got {:?}",
+                spec
+            );
+        }
+        let prompt = match parts.next().map(str::trim) {
+            Some(prompt) if !prompt.is_empty() => prompt.to_string(),
+            _ => format!("Start the {} phase", name),
+        };
+        Ok(Self {
+            name: name.to_string(),
+            duration_s,
+            prompt,
+        })
+    }
+}
+
+/// Machine a benchmark ran on.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Machine {
+    pub hostname: String,
+    /// Kernel release (e.g., "6.8.0-31-generic")
+    pub kernel: String,
+    /// DMI product name, if available
+    pub product: Option<String>,
+}
+
+impl Machine {
+    fn read(roots: &FsRoots) -> Self {
+        let read = |path: std::path::PathBuf| {
+            fs::read_to_string(path)
+                .ok()
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+        };
+        Self {
+            hostname: read(roots.proc_path("sys/kernel/hostname")).unwrap_or_default(),
+            kernel: read(roots.proc_path("sys/kernel/osrelease")).unwrap_or_default(),
+            product: read(roots.sys_path("class/dmi/id/product_name")),
+        }
+    }
+
+    fn describe(&self) -> String {
+        let or_unknown = |s: &str| {
+            if s.is_empty() {
+                "unknown".to_string()
+            } else {
+                s.to_string()
+            }
+        };
+        let mut out = format!(
+            "{}, kernel {}",
+            or_unknown(&self.hostname),
+            or_unknown(&self.kernel)
+        );
+        if let Some(product) = &self.product {
+            let _ = write!(out, ", {}", product);
+        }
+        out
+    }
+}
+
+/// Rates of one source during one phase.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceResult {
+    /// Key matching the source across machines and boots (see `compare::source_key`)
+    pub key: String,
+    pub irq: String,
+    pub name: String,
+    pub device_type: String,
+    pub controller: String,
+    pub is_controller: bool,
+    pub interrupts: u64,
+    pub mean_rate: f64,
+    /// Highest rate of a single sampling interval
+    pub peak_rate: f64,
+}
+
+/// Result of one phase.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhaseResult {
+    pub name: String,
+    pub prompt: String,
+    /// Time sampled, excluding intervals spanning a suspend
+    pub elapsed_s: f64,
+    pub intervals: u32,
+    pub sources: Vec<SourceResult>,
+}
+
+impl PhaseResult {
+    fn new(phase: &Phase, totals: &Totals) -> Self {
+        Self {
+            name: phase.name.clone(),
+            prompt: phase.prompt.clone(),
+            elapsed_s: totals.elapsed_s,
+            intervals: totals.intervals,
+            sources: totals
+                .sources
+                .iter()
+                .enumerate()
+                .map(|(idx, source)| SourceResult {
+                    key: source_key(source),
+                    irq: source.irq.clone(),
+                    name: source.name.clone(),
+                    device_type: source.device_type.clone(),
+                    controller: source.controller.clone(),
+                    is_controller: source.is_controller,
+                    interrupts: totals.deltas[idx],
+                    mean_rate: totals.mean(idx),
+                    peak_rate: totals.peaks[idx],
+                })
+                .collect(),
+        }
+    }
+
+    fn find(&self, key: &str) -> Option<&SourceResult> {
+        self.sources.iter().find(|s| s.key == key)
+    }
+
+    /// Controller IRQs per HID IRQ over the phase, `None` while the HID devices were idle.
+    fn ratio(&self, controller: &str) -> Option<f64> {
+        let count = |is_controller: bool| -> u64 {
+            self.sources
+                .iter()
+                .filter(|s| s.controller == controller && s.is_controller == is_controller)
+                .map(|s| s.interrupts)
+                .sum()
+        };
+        let hid = count(false);
+        (hid > 0).then(|| count(true) as f64 / hid as f64)
+    }
+}
+
+/// A saved benchmark run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkFile {
+    /// Always FORMAT_NAME
+    pub format: String,
+    pub version: u32,
+    pub machine: Machine,
+    pub interval_ms: u64,
+    pub topology: I2cTopology,
+    pub phases: Vec<PhaseResult>,
+}
+
+impl BenchmarkFile {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let value: serde_json::Value = serde_json::from_str(&content)
+            .with_context(|| format!("invalid benchmark file {}", path.display()))?;
+        let format = value.get("format").and_then(|f| f.as_str()).unwrap_or("");
+        if format != FORMAT_NAME {
+            bail!(
+                "{} is not a benchmark file (format {:?})",
+                path.display(),
+                format
+            );
+        }
+        let file: Self = serde_json::from_value(value)
+            .with_context(|| format!("invalid benchmark file {}", path.display()))?;
+        if file.version > FORMAT_VERSION {
+            bail!(
+                "benchmark version {} is newer than supported version {}",
+                file.version,
+                FORMAT_VERSION
+            );
+        }
+        Ok(file)
+    }
+
+    fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json + "\n").with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    /// Per-phase tables, with the change against a baseline run when given.
+    fn render(&self, baseline: Option<&BenchmarkFile>) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "\n=== Benchmark Results ===\n");
+        let _ = writeln!(out, "Machine: {}", self.machine.describe());
+        if let Some(baseline) = baseline {
+            let _ = writeln!(out, "Baseline: {}", baseline.machine.describe());
+        }
+
+        for phase in &self.phases {
+            let base_phase = baseline.and_then(|b| b.phases.iter().find(|p| p.name == phase.name));
+            let _ = writeln!(
+                out,
+                "\n--- {} ({:.1}s, {} intervals): {} ---\n",
+                phase.name, phase.elapsed_s, phase.intervals, phase.prompt
+            );
+            let _ = write!(
+                out,
+                "{:<40} {:>12} {:>12} {:>8}",
+                "Source", "Mean", "Peak", "Ratio"
+            );
+            if baseline.is_some() {
+                let _ = write!(out, " {:>12} {:>8}", "Baseline", "Change");
+            }
+            let _ = writeln!(out);
+            let _ = writeln!(
+                out,
+                "{}",
+                "-".repeat(if baseline.is_some() { 96 } else { 75 })
+            );
+
+            for source in &phase.sources {
+                let (name, ratio) = if source.is_controller {
+                    (
+                        source.controller.clone(),
+                        format_ratio(phase.ratio(&source.controller)),
+                    )
+                } else {
+                    (format!("  \u{2514}\u{2500} {}", source.name), String::new())
+                };
+                let _ = write!(
+                    out,
+                    "{:<40} {:>10.1}/s {:>10.1}/s {:>8}",
+                    name, source.mean_rate, source.peak_rate, ratio
+                );
+                if let Some(base_phase) = base_phase {
+                    let before = base_phase.find(&source.key).map(|s| s.mean_rate);
+                    let _ = write!(
+                        out,
+                        " {:>12} {:>8}",
+                        before
+                            .map(|r| format!("{:.1}/s", r))
+                            .unwrap_or_else(|| "-".to_string()),
+                        format_change(before, Some(source.mean_rate))
+                    );
+                }
+                let _ = writeln!(out);
+            }
+        }
+        out
+    }
+}
+
+/// Print a line that replaces the current one on a terminal.
+fn status_line(tty: bool, text: &str) {
+    if tty {
+        print!("\r\x1b[K{}", text);
+        let _ = io::stdout().flush();
+    }
+}
+
+/// Prompt for a phase and count down before it starts.
+fn announce(index: usize, count: usize, phase: &Phase, prepare_s: u64) {
+    println!(
+        "\n[{}/{}] {} ({}s): {}",
+        index + 1,
+        count,
+        phase.name,
+        phase.duration_s,
+        phase.prompt
+    );
+    print!("  Starting in");
+    for remaining in (1..=prepare_s).rev() {
+        print!(" {}...", remaining);
+        let _ = io::stdout().flush();
+        thread::sleep(Duration::from_secs(1));
+    }
+    println!(" go!");
+}
+
+/// Run the phases, print per-phase tables and save them to `output`.
+pub fn run(
+    phases: &[Phase],
+    interval_ms: u64,
+    prepare_s: u64,
+    output: &Path,
+    backend: Backend,
+    roots: &FsRoots,
+) -> Result<()> {
+    let topology = discovery::discover(roots)?;
+    let sources = topology.all_sources();
+    if sources.is_empty() {
+        bail!("No I2C-related interrupt sources found.");
+    }
+    let mut sampler = backend.open(&topology, roots);
+    let tty = io::stdout().is_terminal();
+
+    println!("=== I2C Interrupt Benchmark ===");
+    println!(
+        "{} phases, sampling {} sources every {}ms",
+        phases.len(),
+        sources.len(),
+        interval_ms
+    );
+
+    let mut results = Vec::with_capacity(phases.len());
+    for (index, phase) in phases.iter().enumerate() {
+        announce(index, phases.len(), phase, prepare_s);
+
+        let initial = sampler
+            .sample()?
+            .context("no interrupt snapshots available")?;
+        let mut tracker = RateTracker::new(&sources, &initial, interval_ms);
+        let mut totals = Totals::new(sources.clone());
+        let started = Instant::now();
+        let mut schedule = Schedule::starting_at(started, Duration::from_millis(interval_ms));
+        let mut suspends = 0;
+        // Stop at the sample closest to the requested duration
+        let stop_at = phase.duration_s - interval_ms as f64 / 2000.0;
+
+        loop {
+            let remaining = phase.duration_s - started.elapsed().as_secs_f64();
+            status_line(
+                tty,
+                &format!(
+                    "  Recording {}... {:.0}s left",
+                    phase.name,
+                    remaining.max(0.0).ceil()
+                ),
+            );
+            schedule.wait();
+            schedule.advance(Instant::now());
+            let Some(current) = sampler.sample()? else {
+                break;
+            };
+            match tracker.next(&current) {
+                Interval::Rates { dt_s, sources } => totals.add(dt_s, &sources),
+                Interval::Suspended(_) => suspends += 1,
+            }
+            if current.timestamp - initial.timestamp >= stop_at {
+                break;
+            }
+        }
+        status_line(tty, "");
+        print!("  Done: {:.1}s sampled", totals.elapsed_s);
+        if suspends > 0 {
+            print!(", {} intervals spanning a suspend skipped", suspends);
+        }
+        println!();
+
+        results.push(PhaseResult::new(phase, &totals));
+    }
+
+    let file = BenchmarkFile {
+        format: FORMAT_NAME.to_string(),
+        version: FORMAT_VERSION,
+        machine: Machine::read(roots),
+        interval_ms,
+        topology,
+        phases: results,
+    };
+    print!("{}", file.render(None));
+    file.save(output)?;
+    println!("\nSaved to {}", output.display());
+    Ok(())
+}
+
+/// Print the results of a saved benchmark against a baseline run.
+pub fn compare(baseline_path: &Path, path: &Path) -> Result<()> {
+    let baseline = BenchmarkFile::load(baseline_path)?;
+    let file = BenchmarkFile::load(path)?;
+    print!("{}", file.render(Some(&baseline)));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture;
+    use crate::rates::SourceRate;
+
+    /// A run whose touchpad phase has the given per-second controller and HID deltas.
+    fn run(hostname: &str, intervals: &[(u64, u64)]) -> BenchmarkFile {
+        let topology = fixture::touchpad_topology();
+        let phase = Phase::new("touchpad", 2.0, "Swipe");
+        let mut totals = Totals::new(topology.all_sources());
+        for &(ctrl, hid) in intervals {
+            let rate = |delta| SourceRate {
+                count: 0,
+                delta,
+                rate: delta as f64,
+            };
+            totals.add(1.0, &[rate(ctrl), rate(hid)]);
+        }
+        BenchmarkFile {
+            format: FORMAT_NAME.to_string(),
+            version: FORMAT_VERSION,
+            machine: Machine {
+                hostname: hostname.to_string(),
+                kernel: "6.8.0".to_string(),
+                product: None,
+            },
+            interval_ms: 1000,
+            topology,
+            phases: vec![PhaseResult::new(&phase, &totals)],
+        }
+    }
+
+    #[test]
+    fn test_parse_phase() {
+        let phase: Phase = "swipe:15:Swipe with: two fingers".parse().unwrap();
+        assert_eq!(phase, Phase::new("swipe", 15.0, "Swipe with: two fingers"));
+        let phase: Phase = "idle:5".parse().unwrap();
+        assert_eq!(phase.prompt, "Start the idle phase");
+        assert!("idle".parse::<Phase>().is_err());
+        assert!("idle:0".parse::<Phase>().is_err());
+        assert!(":10".parse::<Phase>().is_err());
+    }
+
+    #[test]
+    fn test_phase_results_and_comparison() {
+        let baseline = run("laptop-a", &[(3400, 100), (3400, 100)]);
+        let phase = &baseline.phases[0];
+        assert_eq!(phase.sources[0].key, "i2c_designware.5");
+        assert_eq!(phase.sources[1].key, "i2c_designware.5/PIXA3854:00");
+        assert_eq!(phase.ratio("i2c_designware.5"), Some(34.0));
+
+        // Round trip through the saved format
+        let json = serde_json::to_string(&run("laptop-b", &[(500, 100), (300, 100)])).unwrap();
+        let file: BenchmarkFile = serde_json::from_str(&json).unwrap();
+        assert_eq!(file.phases[0].sources[0].peak_rate, 500.0);
+
+        let text = file.render(Some(&baseline));
+        assert!(text.contains("Machine: laptop-b, kernel 6.8.0"));
+        assert!(text.contains("Baseline: laptop-a, kernel 6.8.0"));
+        assert!(text.contains("--- touchpad (2.0s, 2 intervals): Swipe ---"));
+        let controller = text
+            .lines()
+            .find(|l| l.starts_with("i2c_designware.5"))
+            .unwrap();
+        assert!(controller.contains("400.0/s"));
+        assert!(controller.contains("4.0:1"));
+        assert!(controller.contains("3400.0/s"));
+        assert!(controller.contains("-88.2%"));
+    }
+}
@@ -0,0 +1,447 @@
+//! Oscilloscope-style capture: keep a ring buffer of snapshots and write the
+//! window around each trigger to its own recording.
+
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result, bail};
+
+use crate::check::MaxRule;
+use crate::discovery::{I2cTopology, InterruptSourceInfo};
+use crate::rates::{Interval, RateTracker, SourceRate};
+use crate::recording::{self, Header, TriggerInfo};
+use crate::roots::FsRoots;
+use crate::sampler::{SampleSource, Snapshot};
+use crate::schedule::Schedule;
+use crate::thresholds;
+
+/// Allowed range of the controller/HID ratio of the controllers matching a key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RatioBand {
+    pub key: String,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl FromStr for RatioBand {
+    type Err = anyhow::Error;
+
+    /// Parse `KEY=MIN:MAX`, where either bound may be left out (e.g., `controller=:40`).
+    fn from_str(spec: &str) -> Result<Self> {
+        let usage = || format!("expected KEY=MIN:MAX, got {:?}", spec);
+        let (key, band) = spec.split_once('=').with_context(usage)?;
+        let (min, max) = band.split_once(':').with_context(usage)?;
+        let bound = |value: &str| -> Result<Option<f64>> {
+            let value = value.trim();
+            if value.is_empty() {
+                return Ok(None);
+            }
+            let bound: f64 = value
+                .parse()
+                .with_context(|| format!("invalid number {:?} in {:?}", value, spec))?;
+            if bound.is_nan() || bound < 0.0 {
+                bail!("expected non-negative bounds, got {:?}", spec);
+            }
+            Ok(Some(bound))
+        };
+        let band = Self {
+            key: key.trim().to_string(),
+            min: bound(min)?,
+            max: bound(max)?,
+        };
+        if band.key.is_empty() || (band.min.is_none() && band.max.is_none()) {
+            bail!("{}", usage());
+        }
+        if let (Some(min), Some(max)) = (band.min, band.max)
+            && min > max
+        {
+            bail!("MIN is above MAX in {:?}", spec);
+        }
+        Ok(band)
+    }
+}
+
+/// Conditions that start a capture.
+pub struct Triggers {
+    pub max_rates: Vec<MaxRule>,
+    pub ratio_bands: Vec<RatioBand>,
+}
+
+impl Triggers {
+    /// Describe every condition the interval meets, or `None` if nothing fired.
+    ///
+    /// Ratios are only evaluated for intervals in which the controller's HID
+    /// devices fired.
+    fn fired(&self, sources: &[InterruptSourceInfo], rates: &[SourceRate]) -> Option<String> {
+        let mut reasons = Vec::new();
+
+        for rule in &self.max_rates {
+            for (source, rate) in sources.iter().zip(rates) {
+                if rate.rate > rule.max && thresholds::key_specificity(&rule.key, source).is_some()
+                {
+                    reasons.push(format!(
+                        "rate {}>{}: {} (IRQ {}) at {:.1}/s",
+                        rule.key, rule.max, source.name, source.irq, rate.rate
+                    ));
+                }
+            }
+        }
+
+        for band in &self.ratio_bands {
+            let controllers = sources
+                .iter()
+                .filter(|s| s.is_controller)
+                .filter(|s| thresholds::key_specificity(&band.key, s).is_some());
+            for controller in controllers {
+                let mut ctrl = 0;
+                let mut hid = 0;
+                for (source, rate) in sources.iter().zip(rates) {
+                    if source.controller != controller.controller {
+                        continue;
+                    }
+                    if source.is_controller {
+                        ctrl += rate.delta;
+                    } else {
+                        hid += rate.delta;
+                    }
+                }
+                if hid == 0 {
+                    continue;
+                }
+                let ratio = ctrl as f64 / hid as f64;
+                if band.min.is_some_and(|min| ratio < min)
+                    || band.max.is_some_and(|max| ratio > max)
+                {
+                    let format_bound =
+                        |b: Option<f64>| b.map(|b| b.to_string()).unwrap_or_default();
+                    reasons.push(format!(
+                        "ratio {} outside {}:{}: {} at {:.1}:1",
+                        band.key,
+                        format_bound(band.min),
+                        format_bound(band.max),
+                        controller.controller,
+                        ratio
+                    ));
+                }
+            }
+        }
+
+        (!reasons.is_empty()).then(|| reasons.join("; "))
+    }
+}
+
+/// Sampling, window and output settings of a capture session.
+#[derive(Debug, Clone)]
+pub struct CaptureConfig {
+    /// Sampling interval in milliseconds
+    pub interval_ms: u64,
+    /// Seconds kept before a trigger
+    pub pre_s: f64,
+    /// Seconds kept after a trigger
+    pub post_s: f64,
+    /// Directory the capture files are written to
+    pub output_dir: PathBuf,
+    /// Stop after this many captures (0 = until interrupted)
+    pub max_captures: u32,
+}
+
+/// Snapshots around one trigger.
+#[derive(Debug, Clone)]
+struct Capture {
+    trigger: TriggerInfo,
+    snapshots: Vec<Snapshot>,
+}
+
+/// Ring buffer of recent snapshots and the capture in progress.
+struct Scope {
+    pre_s: f64,
+    /// A capture ends at the first snapshot at least this long after the trigger
+    post_s: f64,
+    buffer: VecDeque<Snapshot>,
+    active: Option<Capture>,
+    /// Cleared by a trigger, set again once no trigger condition holds
+    armed: bool,
+}
+
+impl Scope {
+    fn new(pre_s: f64, post_s: f64) -> Self {
+        Self {
+            pre_s,
+            post_s,
+            buffer: VecDeque::new(),
+            active: None,
+            armed: true,
+        }
+    }
+
+    fn is_armed(&self) -> bool {
+        self.armed && self.active.is_none()
+    }
+
+    /// Add a snapshot, with the reason if it fired a trigger, and return a
+    /// capture once its post-trigger window is complete.
+    fn push(&mut self, snapshot: Snapshot, fired: Option<String>) -> Option<Capture> {
+        // Keep the last snapshot before the window as the baseline of its first rate
+        let window_start = snapshot.timestamp - self.pre_s;
+        self.buffer.push_back(snapshot.clone());
+        while self
+            .buffer
+            .get(1)
+            .is_some_and(|s| s.timestamp <= window_start)
+        {
+            self.buffer.pop_front();
+        }
+
+        if let Some(active) = &mut self.active {
+            active.snapshots.push(snapshot);
+        } else if let Some(reason) = &fired
+            && self.armed
+        {
+            self.active = Some(Capture {
+                trigger: TriggerInfo {
+                    timestamp: snapshot.timestamp,
+                    reason: reason.clone(),
+                },
+                snapshots: self.buffer.iter().cloned().collect(),
+            });
+            self.armed = false;
+        }
+        if fired.is_none() {
+            self.armed = true;
+        }
+
+        let done = self.active.as_ref().is_some_and(|active| {
+            let last = active.snapshots.last().map_or(0.0, |s| s.timestamp);
+            last - active.trigger.timestamp >= self.post_s
+        });
+        if done { self.active.take() } else { None }
+    }
+
+    /// The capture in progress, cut short.
+    fn finish(&mut self) -> Option<Capture> {
+        self.active.take()
+    }
+}
+
+/// Write a capture as a recording and return its path.
+fn write_capture(
+    config: &CaptureConfig,
+    index: u32,
+    topology: &I2cTopology,
+    capture: &Capture,
+) -> Result<PathBuf> {
+    let unix_s = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let path = config
+        .output_dir
+        .join(format!("capture-{}-{}.jsonl", unix_s, index));
+    let mut header = Header::new(config.interval_ms, topology);
+    header.trigger = Some(capture.trigger.clone());
+    let mut writer = recording::Writer::create(&path, &header)?;
+    for snapshot in &capture.snapshots {
+        writer.write(snapshot)?;
+    }
+    Ok(path)
+}
+
+/// Write a capture and report it, returning whether it was written.
+///
+/// A failed write is logged rather than returned, so one full disk or
+/// unwritable file does not end an unattended session.
+fn save_capture(
+    config: &CaptureConfig,
+    index: u32,
+    topology: &I2cTopology,
+    capture: &Capture,
+) -> bool {
+    match write_capture(config, index, topology, capture) {
+        Ok(path) => {
+            let first = capture.snapshots.first().map_or(0.0, |s| s.timestamp);
+            let last = capture.snapshots.last().map_or(0.0, |s| s.timestamp);
+            println!(
+                "  wrote {} snapshots ({:.1}s before, {:.1}s after the trigger) to {}",
+                capture.snapshots.len(),
+                capture.trigger.timestamp - first,
+                last - capture.trigger.timestamp,
+                path.display()
+            );
+            true
+        }
+        Err(e) => {
+            eprintln!("failed to write capture: {:#}", e);
+            false
+        }
+    }
+}
+
+/// Create the output directory and make sure captures can be written to it.
+fn prepare_output_dir(dir: &Path) -> Result<()> {
+    fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    let probe = dir.join(format!(".capture-probe-{}", std::process::id()));
+    fs::write(&probe, b"").with_context(|| format!("{} is not writable", dir.display()))?;
+    let _ = fs::remove_file(&probe);
+    Ok(())
+}
+
+/// Sample into a ring buffer and write the window around every trigger.
+pub fn run(
+    config: &CaptureConfig,
+    triggers: &Triggers,
+    sample_source: &SampleSource,
+    roots: &FsRoots,
+) -> Result<()> {
+    let interval_ms = config.interval_ms;
+    let (topology, mut sampler) = sample_source.open(roots)?;
+    let sources = topology.all_sources();
+    if sources.is_empty() {
+        bail!("No I2C-related interrupt sources found.");
+    }
+    prepare_output_dir(&config.output_dir)?;
+
+    println!(
+        "Armed: watching {} sources every {}ms, capturing {}s before and {}s after each trigger to {} (Ctrl-C to stop)",
+        sources.len(),
+        interval_ms,
+        config.pre_s,
+        config.post_s,
+        config.output_dir.display()
+    );
+
+    let initial = sampler
+        .sample()?
+        .context("no interrupt snapshots available")?;
+    let mut tracker = RateTracker::new(&sources, &initial, interval_ms);
+    // Stop at the sample closest to the requested window
+    let mut scope = Scope::new(config.pre_s, config.post_s - interval_ms as f64 / 2000.0);
+    scope.push(initial, None);
+    let mut schedule = Schedule::new(Duration::from_millis(interval_ms));
+    // Triggers numbering the files, and captures actually written
+    let mut captures = 0;
+    let mut written = 0;
+
+    loop {
+        if sampler.is_live() {
+            schedule.wait();
+            schedule.advance(Instant::now());
+        }
+        let Some(current) = sampler.sample()? else {
+            break;
+        };
+        let fired = match tracker.next(&current) {
+            Interval::Rates { sources: rates, .. } => triggers.fired(&sources, &rates),
+            Interval::Suspended(gap) => {
+                println!("-- {} --", gap.describe());
+                None
+            }
+        };
+        let timestamp = current.timestamp;
+        let was_armed = scope.is_armed();
+        if let Some(reason) = &fired
+            && was_armed
+        {
+            println!("[{:.1}] triggered: {}", timestamp, reason);
+        }
+        let captured = scope.push(current, fired);
+        if !was_armed && scope.is_armed() {
+            println!("[{:.1}] re-armed", timestamp);
+        }
+        if let Some(capture) = captured {
+            captures += 1;
+            if save_capture(config, captures, &topology, &capture) {
+                written += 1;
+            }
+            if config.max_captures > 0 && captures >= config.max_captures {
+                break;
+            }
+        }
+    }
+
+    if let Some(capture) = scope.finish() {
+        captures += 1;
+        println!("Sampling ended before the post-trigger window was complete");
+        if save_capture(config, captures, &topology, &capture) {
+            written += 1;
+        }
+    }
+    println!(
+        "{} of {} captures written ({} late, {} missed samples).",
+        written, captures, schedule.late, schedule.missed
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture;
+
+    fn rate(delta: u64) -> SourceRate {
+        SourceRate {
+            count: 0,
+            delta,
+            rate: delta as f64,
+        }
+    }
+
+    #[test]
+    fn test_triggers() {
+        let triggers = Triggers {
+            max_rates: vec!["touchpad=200".parse().unwrap()],
+            ratio_bands: vec!["controller=2:40".parse().unwrap()],
+        };
+        let sources = fixture::touchpad_sources();
+        assert_eq!(triggers.fired(&sources, &[rate(3400), rate(100)]), None);
+        assert_eq!(
+            triggers.fired(&sources, &[rate(500), rate(500)]).unwrap(),
+            "rate touchpad>200: PIXA3854:00 (IRQ 203) at 500.0/s; \
+             ratio controller outside 2:40: i2c_designware.5 at 1.0:1"
+        );
+        // No ratio without HID interrupts
+        assert_eq!(triggers.fired(&sources, &[rate(500), rate(0)]), None);
+
+        let band: RatioBand = "i2c_designware.5=:40".parse().unwrap();
+        assert_eq!((band.min, band.max), (None, Some(40.0)));
+        assert!("controller=40".parse::<RatioBand>().is_err());
+        assert!("controller=:".parse::<RatioBand>().is_err());
+        assert!("controller=40:2".parse::<RatioBand>().is_err());
+    }
+
+    #[test]
+    fn test_scope_windows_and_rearm() {
+        let mut scope = Scope::new(2.0, 1.0);
+        let fire = |t: f64| (t == 5.0 || t == 6.0 || t == 9.0).then(|| "storm".to_string());
+        let mut captures = Vec::new();
+        for t in 0..12 {
+            let t = t as f64;
+            if let Some(capture) = scope.push(fixture::snapshot(t, &[]), fire(t)) {
+                captures.push(capture);
+            }
+        }
+
+        // The trigger at 6.0 falls within the first capture, 9.0 starts a second one
+        assert_eq!(captures.len(), 2);
+        let timestamps: Vec<f64> = captures[0].snapshots.iter().map(|s| s.timestamp).collect();
+        assert_eq!(timestamps, [3.0, 4.0, 5.0, 6.0]);
+        assert_eq!(captures[0].trigger.timestamp, 5.0);
+        assert_eq!(captures[1].trigger.timestamp, 9.0);
+        assert_eq!(captures[1].snapshots.len(), 4);
+        assert!(scope.finish().is_none());
+
+        // A condition that keeps holding after the window does not re-trigger
+        let mut scope = Scope::new(1.0, 1.0);
+        let captures = (0..6)
+            .filter_map(|t| {
+                scope.push(
+                    fixture::snapshot(t as f64, &[]),
+                    (t >= 2).then(|| "storm".into()),
+                )
+            })
+            .count();
+        assert_eq!(captures, 1);
+        assert!(!scope.is_armed());
+    }
+}
@@ -0,0 +1,274 @@
+//! Pass/fail checks of interrupt rates for CI pipelines.
+
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+
+use crate::discovery::InterruptSourceInfo;
+use crate::rates::{Interval, RateTracker, Totals};
+use crate::roots::FsRoots;
+use crate::sampler::SampleSource;
+use crate::schedule::Schedule;
+use crate::thresholds;
+
+/// Exit code when every check passed.
+pub const EXIT_PASS: u8 = 0;
+/// Exit code when a check failed (1 is used for errors, 2 for usage errors).
+pub const EXIT_FAIL: u8 = 3;
+/// Exit code when no I2C interrupt sources were found.
+pub const EXIT_NO_DEVICES: u8 = 4;
+
+/// An upper bound on a value of the sources matching a key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaxRule {
+    pub key: String,
+    pub max: f64,
+}
+
+impl FromStr for MaxRule {
+    type Err = anyhow::Error;
+
+    fn from_str(spec: &str) -> Result<Self> {
+        let (key, max) = spec
+            .split_once('=')
+            .with_context(|| format!("expected KEY=MAX, got {:?}", spec))?;
+        let max: f64 = max
+            .trim()
+            .parse()
+            .with_context(|| format!("invalid number {:?} in {:?}", max, spec))?;
+        if key.trim().is_empty() || max.is_nan() || max < 0.0 {
+            anyhow::bail!("expected KEY=MAX with a non-negative MAX, got {:?}", spec);
+        }
+        Ok(Self {
+            key: key.trim().to_string(),
+            max,
+        })
+    }
+}
+
+/// Result of one rule against one source.
+#[derive(Debug, Clone, PartialEq)]
+struct Outcome {
+    /// The rule as given (e.g., "max-rate touchpad=200")
+    rule: String,
+    /// The source checked and the measured value, or why nothing was checked
+    detail: String,
+    pass: bool,
+}
+
+/// Check every rule against every source it matches.
+///
+/// A rule that matches no source fails, so a typo cannot pass silently.
+fn evaluate(totals: &Totals, max_rates: &[MaxRule], max_ratios: &[MaxRule]) -> Vec<Outcome> {
+    let mut outcomes = Vec::new();
+
+    for rule in max_rates {
+        let name = format!("max-rate {}={}", rule.key, rule.max);
+        let mut matched = false;
+        for (idx, source) in totals.sources.iter().enumerate() {
+            if thresholds::key_specificity(&rule.key, source).is_none() {
+                continue;
+            }
+            matched = true;
+            let mean = totals.mean(idx);
+            outcomes.push(Outcome {
+                rule: name.clone(),
+                detail: format!(
+                    "{} (IRQ {}): peak {:.1}/s, mean {:.1}/s",
+                    source.name, source.irq, totals.peaks[idx], mean
+                ),
+                pass: totals.peaks[idx] <= rule.max,
+            });
+        }
+        if !matched {
+            outcomes.push(Outcome {
+                rule: name,
+                detail: "no matching source".to_string(),
+                pass: false,
+            });
+        }
+    }
+
+    for rule in max_ratios {
+        let name = format!("max-ratio {}={}", rule.key, rule.max);
+        let controllers: Vec<&InterruptSourceInfo> = totals
+            .sources
+            .iter()
+            .filter(|s| s.is_controller)
+            .filter(|s| thresholds::key_specificity(&rule.key, s).is_some())
+            .collect();
+        if controllers.is_empty() {
+            outcomes.push(Outcome {
+                rule: name.clone(),
+                detail: "no matching controller".to_string(),
+                pass: false,
+            });
+        }
+        for controller in controllers {
+            let outcome = match totals.ratio(&controller.controller) {
+                Some(ratio) => Outcome {
+                    rule: name.clone(),
+                    detail: format!("{}: {:.1}:1", controller.controller, ratio),
+                    pass: ratio <= rule.max,
+                },
+                None => Outcome {
+                    rule: name.clone(),
+                    detail: format!(
+                        "{}: no HID interrupts, ratio unknown",
+                        controller.controller
+                    ),
+                    pass: false,
+                },
+            };
+            outcomes.push(outcome);
+        }
+    }
+
+    outcomes
+}
+
+/// Sample for `duration_s` seconds, print a report and return the exit code.
+pub fn run(
+    duration_s: f64,
+    interval_ms: u64,
+    max_rates: &[MaxRule],
+    max_ratios: &[MaxRule],
+    sample_source: &SampleSource,
+    roots: &FsRoots,
+) -> Result<u8> {
+    let (topology, mut sampler) = sample_source.open(roots)?;
+    let sources = topology.all_sources();
+    if sources.is_empty() {
+        println!("No I2C-related interrupt sources found.");
+        return Ok(EXIT_NO_DEVICES);
+    }
+
+    println!("=== I2C Interrupt Check ===");
+    println!(
+        "Sampling {} sources for {}s every {}ms",
+        sources.len(),
+        duration_s,
+        interval_ms
+    );
+
+    let initial = sampler
+        .sample()?
+        .context("no interrupt snapshots available")?;
+    let mut tracker = RateTracker::new(&sources, &initial, interval_ms);
+    let mut totals = Totals::new(sources);
+    let mut schedule = Schedule::new(Duration::from_millis(interval_ms));
+    let mut suspends = 0;
+    // Stop at the sample closest to the requested duration
+    let stop_at = duration_s - interval_ms as f64 / 2000.0;
+
+    loop {
+        if sampler.is_live() {
+            schedule.wait();
+            schedule.advance(Instant::now());
+        }
+        let Some(current) = sampler.sample()? else {
+            break;
+        };
+        match tracker.next(&current) {
+            Interval::Rates { dt_s, sources } => totals.add(dt_s, &sources),
+            Interval::Suspended(_) => suspends += 1,
+        }
+        if current.timestamp - initial.timestamp >= stop_at {
+            break;
+        }
+    }
+
+    if totals.intervals == 0 {
+        anyhow::bail!("no sampling intervals to check");
+    }
+
+    let outcomes = evaluate(&totals, max_rates, max_ratios);
+    let width = outcomes.iter().map(|o| o.rule.len()).max().unwrap_or(0);
+    println!();
+    for outcome in &outcomes {
+        println!(
+            "{}  {:<width$}  {}",
+            if outcome.pass { "PASS" } else { "FAIL" },
+            outcome.rule,
+            outcome.detail
+        );
+    }
+
+    let failed = outcomes.iter().filter(|o| !o.pass).count();
+    print!(
+        "\n{} of {} checks failed over {:.1}s ({} intervals",
+        failed,
+        outcomes.len(),
+        totals.elapsed_s,
+        totals.intervals
+    );
+    if suspends > 0 {
+        print!(", {} spanning a suspend skipped", suspends);
+    }
+    println!(")");
+
+    if failed > 0 {
+        println!("Result: FAIL");
+        Ok(EXIT_FAIL)
+    } else {
+        println!("Result: PASS");
+        Ok(EXIT_PASS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture;
+    use crate::rates::SourceRate;
+
+    fn rate(delta: u64) -> SourceRate {
+        SourceRate {
+            count: 0,
+            delta,
+            rate: delta as f64,
+        }
+    }
+
+    #[test]
+    fn test_evaluate_rules() {
+        let mut totals = Totals::new(fixture::touchpad_sources());
+        totals.add(1.0, &[rate(3400), rate(100)]);
+        totals.add(1.0, &[rate(1700), rate(50)]);
+
+        let rule = |spec: &str| spec.parse::<MaxRule>().unwrap();
+        let outcomes = evaluate(
+            &totals,
+            &[rule("touchpad=120"), rule("keyboard=50")],
+            &[rule("i2c_designware.5=6")],
+        );
+        let results: Vec<_> = outcomes
+            .iter()
+            .map(|o| (o.rule.as_str(), o.detail.as_str(), o.pass))
+            .collect();
+        assert_eq!(
+            results,
+            [
+                (
+                    "max-rate touchpad=120",
+                    "PIXA3854:00 (IRQ 203): peak 100.0/s, mean 75.0/s",
+                    true
+                ),
+                ("max-rate keyboard=50", "no matching source", false),
+                (
+                    "max-ratio i2c_designware.5=6",
+                    "i2c_designware.5: 34.0:1",
+                    false
+                ),
+            ]
+        );
+
+        // The peak interval counts, not the mean
+        let outcomes = evaluate(&totals, &[rule("203=80")], &[]);
+        assert!(!outcomes[0].pass);
+
+        assert!("touchpad".parse::<MaxRule>().is_err());
+        assert!("touchpad=-1".parse::<MaxRule>().is_err());
+    }
+}
@@ -0,0 +1,383 @@
+//! Statistical A/B comparison of two recordings.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::Result;
+
+use crate::discovery::InterruptSourceInfo;
+use crate::rates::{Interval, RateTracker};
+use crate::recording::Recording;
+use crate::stats;
+
+/// Key identifying the same source across boots, where IRQ numbers may differ.
+///
+/// HID devices are keyed by their display name, which includes the client
+/// address when device-tree devices share a compatible string.
+pub fn source_key(info: &InterruptSourceInfo) -> String {
+    if info.is_controller {
+        info.controller.clone()
+    } else {
+        format!("{}/{}", info.controller, info.name)
+    }
+}
+
+/// Counter deltas of one recording, per interval between consecutive snapshots.
+struct Run {
+    sources: Vec<InterruptSourceInfo>,
+    /// Interval lengths in seconds
+    durations: Vec<f64>,
+    /// Per-interval deltas keyed by source key
+    deltas: HashMap<String, Vec<u64>>,
+    /// Intervals dropped because they spanned a system suspend
+    suspended: usize,
+}
+
+impl Run {
+    fn load(path: &Path) -> Result<Self> {
+        let recording = Recording::load(path)?;
+        Self::from_recording(&recording)
+    }
+
+    fn from_recording(recording: &Recording) -> Result<Self> {
+        let sources = recording.topology()?.all_sources();
+        let mut durations = Vec::new();
+        let mut deltas: HashMap<String, Vec<u64>> = HashMap::new();
+        let mut suspended = 0;
+
+        let mut snapshots = recording.snapshots.iter();
+        if let Some(initial) = snapshots.next() {
+            let interval_ms = recording.header.interval_ms.unwrap_or(1000);
+            let mut tracker = RateTracker::new(&sources, initial, interval_ms);
+            let mut prev_timestamp = initial.timestamp;
+
+            for snapshot in snapshots {
+                // Counts of a snapshot without a later timestamp go into the next interval
+                if snapshot.timestamp <= prev_timestamp {
+                    continue;
+                }
+                prev_timestamp = snapshot.timestamp;
+
+                match tracker.next(snapshot) {
+                    Interval::Rates {
+                        dt_s,
+                        sources: rates,
+                    } => {
+                        durations.push(dt_s);
+                        for (source, rate) in sources.iter().zip(&rates) {
+                            deltas
+                                .entry(source_key(source))
+                                .or_default()
+                                .push(rate.delta);
+                        }
+                    }
+                    Interval::Suspended(_) => suspended += 1,
+                }
+            }
+        }
+
+        Ok(Self {
+            sources,
+            durations,
+            deltas,
+            suspended,
+        })
+    }
+
+    /// Number of intervals used, and how many were dropped for spanning a suspend.
+    fn describe_intervals(&self) -> String {
+        if self.suspended == 0 {
+            format!("{} intervals", self.durations.len())
+        } else {
+            format!(
+                "{} intervals, {} spanning a suspend dropped",
+                self.durations.len(),
+                self.suspended
+            )
+        }
+    }
+
+    fn find(&self, key: &str) -> Option<&InterruptSourceInfo> {
+        self.sources.iter().find(|s| source_key(s) == key)
+    }
+
+    /// Per-interval rates of a source.
+    fn rates(&self, key: &str) -> Vec<f64> {
+        self.deltas
+            .get(key)
+            .map(|deltas| {
+                deltas
+                    .iter()
+                    .zip(&self.durations)
+                    .map(|(&delta, &dt)| delta as f64 / dt)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Per-interval (controller, summed HID) deltas of a controller.
+    fn ratio_pairs(&self, controller: &str) -> Vec<(u64, u64)> {
+        let hid_keys: Vec<String> = self
+            .sources
+            .iter()
+            .filter(|s| !s.is_controller && s.controller == controller)
+            .map(source_key)
+            .collect();
+        let Some(controller_deltas) = self.deltas.get(controller) else {
+            return Vec::new();
+        };
+
+        controller_deltas
+            .iter()
+            .enumerate()
+            .map(|(idx, &ctrl)| {
+                let hid = hid_keys
+                    .iter()
+                    .filter_map(|k| self.deltas.get(k).map(|d| d[idx]))
+                    .sum();
+                (ctrl, hid)
+            })
+            .collect()
+    }
+}
+
+/// Controller IRQs per HID IRQ over a set of intervals.
+fn pooled_ratio<'a>(pairs: impl IntoIterator<Item = &'a (u64, u64)>) -> Option<f64> {
+    let (ctrl, hid) = pairs
+        .into_iter()
+        .fold((0u64, 0u64), |(c, h), &(ctrl, hid)| (c + ctrl, h + hid));
+    (hid > 0).then(|| ctrl as f64 / hid as f64)
+}
+
+fn format_rate(rate: Option<f64>) -> String {
+    rate.map(|r| format!("{:.1}/s", r))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+/// Relative change from `before` to `after` (e.g., "+12.5%"), "-" if undefined.
+pub(crate) fn format_change(before: Option<f64>, after: Option<f64>) -> String {
+    match (before, after) {
+        (Some(b), Some(a)) if b > 0.0 => format!("{:+.1}%", (a / b - 1.0) * 100.0),
+        _ => "-".to_string(),
+    }
+}
+
+/// Controller/HID ratio (e.g., "30.0:1"), "-" while the HID devices were idle.
+pub(crate) fn format_ratio(ratio: Option<f64>) -> String {
+    ratio
+        .map(|r| format!("{:.1}:1", r))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn format_ci(ci: Option<(f64, f64)>) -> String {
+    match ci {
+        Some((low, high)) => {
+            let verdict = if low > 0.0 || high < 0.0 {
+                "significant"
+            } else {
+                "not significant"
+            };
+            format!(
+                "95% CI [{:+.1}%, {:+.1}%] {}",
+                low * 100.0,
+                high * 100.0,
+                verdict
+            )
+        }
+        None => "95% CI n/a".to_string(),
+    }
+}
+
+/// Compare two recordings and print per-source statistics and changes.
+pub fn run(before_path: &Path, after_path: &Path) -> Result<()> {
+    let before = Run::load(before_path)?;
+    let after = Run::load(after_path)?;
+
+    println!("=== Interrupt Rate Comparison ===\n");
+    println!(
+        "Before: {} ({})",
+        before_path.display(),
+        before.describe_intervals()
+    );
+    println!(
+        "After:  {} ({})\n",
+        after_path.display(),
+        after.describe_intervals()
+    );
+
+    println!(
+        "{:<34} {:<7} {:>12} {:>12} {:>12}",
+        "Source", "Run", "Mean", "Median", "P95"
+    );
+    println!("{}", "-".repeat(81));
+
+    let mut unmatched = Vec::new();
+
+    for source in &before.sources {
+        let key = source_key(source);
+        if after.find(&key).is_none() {
+            unmatched.push(format!("{} (before only)", key));
+            continue;
+        }
+
+        let name = if source.is_controller {
+            source.controller.clone()
+        } else {
+            format!("  \u{2514}\u{2500} {}", source.name)
+        };
+
+        let rates_before = before.rates(&key);
+        let rates_after = after.rates(&key);
+        let mean_before = stats::mean(&rates_before);
+        let mean_after = stats::mean(&rates_after);
+
+        for (label, rates, name) in [
+            ("before", &rates_before, name.as_str()),
+            ("after", &rates_after, ""),
+        ] {
+            println!(
+                "{:<34} {:<7} {:>12} {:>12} {:>12}",
+                name,
+                label,
+                format_rate(stats::mean(rates)),
+                format_rate(stats::median(rates)),
+                format_rate(stats::percentile(rates, 95.0)),
+            );
+        }
+
+        let ci = stats::relative_change_ci(&rates_before, &rates_after, |rates| {
+            stats::mean(&rates.iter().map(|r| **r).collect::<Vec<_>>())
+        });
+        println!(
+            "{:<34} {:<7} {:>12}  {}",
+            "",
+            "change",
+            format_change(mean_before, mean_after),
+            format_ci(ci)
+        );
+    }
+
+    for source in &after.sources {
+        let key = source_key(source);
+        if before.find(&key).is_none() {
+            unmatched.push(format!("{} (after only)", key));
+        }
+    }
+
+    println!("\n=== Controller/HID Interrupt Ratio ===\n");
+    println!(
+        "{:<34} {:>10} {:>10} {:>10}",
+        "Controller", "Before", "After", "Change"
+    );
+    println!("{}", "-".repeat(81));
+
+    for source in before.sources.iter().filter(|s| s.is_controller) {
+        if after.find(&source.controller).is_none() {
+            continue;
+        }
+        let pairs_before = before.ratio_pairs(&source.controller);
+        let pairs_after = after.ratio_pairs(&source.controller);
+        let ratio_before = pooled_ratio(&pairs_before);
+        let ratio_after = pooled_ratio(&pairs_after);
+
+        let ci = stats::relative_change_ci(&pairs_before, &pairs_after, |pairs| {
+            pooled_ratio(pairs.iter().copied())
+        });
+        println!(
+            "{:<34} {:>10} {:>10} {:>10}  {}",
+            source.controller,
+            format_ratio(ratio_before),
+            format_ratio(ratio_after),
+            format_change(ratio_before, ratio_after),
+            format_ci(ci)
+        );
+    }
+
+    if !unmatched.is_empty() {
+        println!("\nUnmatched sources:");
+        for key in unmatched {
+            println!("  {}", key);
+        }
+    }
+
+    println!();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture;
+    use crate::recording::Header;
+    use crate::suspend::SuspendState;
+
+    /// A recording of one controller and touchpad, using the given IRQ numbers.
+    fn recording(controller_irq: &str, hid_irq: &str, ctrl_per_s: u64) -> Recording {
+        let mut topology = fixture::touchpad_topology();
+        topology.controllers[0].irq = Some(controller_irq.to_string());
+        topology.controllers[0].hid_devices[0].gpio_irq = Some(hid_irq.to_string());
+        let snapshots = (0..5u64)
+            .map(|i| {
+                fixture::snapshot(
+                    i as f64,
+                    &[
+                        (controller_irq, &[(0, i * ctrl_per_s)]),
+                        (hid_irq, &[(0, i * 100)]),
+                    ],
+                )
+            })
+            .collect();
+        Recording {
+            header: Header::new(1000, &topology),
+            snapshots,
+        }
+    }
+
+    #[test]
+    fn test_runs_match_by_name_across_irq_numbers() {
+        let before = Run::from_recording(&recording("21", "203", 3000)).unwrap();
+        let after = Run::from_recording(&recording("27", "190", 400)).unwrap();
+
+        let key = "i2c_designware.5/PIXA3854:00";
+        assert!(before.find(key).is_some() && after.find(key).is_some());
+        assert_eq!(before.rates(key), vec![100.0; 4]);
+
+        assert_eq!(stats::mean(&before.rates("i2c_designware.5")), Some(3000.0));
+        assert_eq!(
+            pooled_ratio(&before.ratio_pairs("i2c_designware.5")),
+            Some(30.0)
+        );
+        assert_eq!(
+            pooled_ratio(&after.ratio_pairs("i2c_designware.5")),
+            Some(4.0)
+        );
+    }
+
+    #[test]
+    fn test_run_drops_suspended_intervals() {
+        let mut recording = recording("21", "203", 3000);
+        // The system suspends for a minute between the third and fourth snapshot,
+        // and the resume path fires a burst of interrupts
+        for (i, snapshot) in recording.snapshots.iter_mut().enumerate() {
+            let suspended = i >= 3;
+            snapshot.suspend = Some(SuspendState {
+                suspended_s: if suspended { 60.0 } else { 0.0 },
+                ..Default::default()
+            });
+            if suspended {
+                for counts in snapshot.counts.values_mut() {
+                    counts[0].1 += 50_000;
+                }
+            }
+        }
+
+        let run = Run::from_recording(&recording).unwrap();
+        assert_eq!(run.suspended, 1);
+        assert_eq!(run.durations, vec![1.0; 3]);
+        assert_eq!(run.rates("i2c_designware.5/PIXA3854:00"), vec![100.0; 3]);
+        assert_eq!(
+            pooled_ratio(&run.ratio_pairs("i2c_designware.5")),
+            Some(30.0)
+        );
+    }
+}
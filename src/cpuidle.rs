@@ -0,0 +1,186 @@
+//! CPU idle-state counters from /sys/devices/system/cpu/cpu*/cpuidle.
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::roots::FsRoots;
+use crate::sampler::{Sampler, Snapshot};
+
+/// Counters of one idle state, summed over all CPUs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdleState {
+    /// State name (e.g., "C10"), taken from the first CPU that has the state
+    pub name: String,
+    /// Times the state was entered (`usage`)
+    pub usage: u64,
+    /// Time spent in the state, in microseconds (`time`)
+    pub time_us: u64,
+    /// CPUs that contributed to the sums
+    pub cpus: u32,
+}
+
+/// Residency and entry rate of one idle state between two snapshots.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdleResidency {
+    pub name: String,
+    /// Share of CPU time spent in the state, in percent
+    pub residency: f64,
+    /// State entries per second, summed over all CPUs
+    pub entry_rate: f64,
+}
+
+fn read_u64(path: &Path) -> Option<u64> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// Numeric suffix of a sysfs entry name (e.g., "state3" -> 3).
+fn index(name: &str, prefix: &str) -> Option<usize> {
+    name.strip_prefix(prefix)?.parse().ok()
+}
+
+/// Read the idle states of all CPUs, summed per state index (shallowest first).
+///
+/// Returns an empty list if cpuidle is unavailable.
+pub fn read_states(roots: &FsRoots) -> Vec<IdleState> {
+    let Ok(cpus) = fs::read_dir(roots.sys_path("devices/system/cpu")) else {
+        return Vec::new();
+    };
+
+    let mut states: Vec<IdleState> = Vec::new();
+    for cpu in cpus.flatten() {
+        let Some(name) = cpu.file_name().to_str().map(str::to_string) else {
+            continue;
+        };
+        if index(&name, "cpu").is_none() {
+            continue;
+        }
+        let Ok(entries) = fs::read_dir(cpu.path().join("cpuidle")) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Some(idx) = entry.file_name().to_str().and_then(|n| index(n, "state")) else {
+                continue;
+            };
+            let dir = entry.path();
+            let (Some(usage), Some(time_us)) =
+                (read_u64(&dir.join("usage")), read_u64(&dir.join("time")))
+            else {
+                continue;
+            };
+            if states.len() <= idx {
+                states.resize_with(idx + 1, || IdleState {
+                    name: String::new(),
+                    usage: 0,
+                    time_us: 0,
+                    cpus: 0,
+                });
+            }
+            let state = &mut states[idx];
+            if state.name.is_empty() {
+                state.name = fs::read_to_string(dir.join("name"))
+                    .map(|n| n.trim().to_string())
+                    .unwrap_or_else(|_| format!("state{}", idx));
+            }
+            state.usage += usage;
+            state.time_us += time_us;
+            state.cpus += 1;
+        }
+    }
+
+    states.retain(|s| s.cpus > 0);
+    states
+}
+
+/// Residency and entry rate of each state over `dt_s` seconds.
+///
+/// States are matched by name, so a state missing from either snapshot is
+/// left out.
+pub fn residency(before: &[IdleState], after: &[IdleState], dt_s: f64) -> Vec<IdleResidency> {
+    if dt_s <= 0.0 {
+        return Vec::new();
+    }
+    after
+        .iter()
+        .filter_map(|state| {
+            let prev = before.iter().find(|s| s.name == state.name)?;
+            let time_us = state.time_us.saturating_sub(prev.time_us);
+            let usage = state.usage.saturating_sub(prev.usage);
+            let cpu_us = state.cpus.max(1) as f64 * dt_s * 1e6;
+            Some(IdleResidency {
+                name: state.name.clone(),
+                residency: (time_us as f64 / cpu_us * 100.0).min(100.0),
+                entry_rate: usage as f64 / dt_s,
+            })
+        })
+        .collect()
+}
+
+/// Adds idle-state counters to the snapshots of another sampler.
+pub struct IdleSampler {
+    inner: Box<dyn Sampler>,
+    roots: FsRoots,
+}
+
+impl IdleSampler {
+    pub fn new(inner: Box<dyn Sampler>, roots: FsRoots) -> Self {
+        Self { inner, roots }
+    }
+}
+
+impl Sampler for IdleSampler {
+    fn sample(&mut self) -> Result<Option<Snapshot>> {
+        let Some(mut snapshot) = self.inner.sample()? else {
+            return Ok(None);
+        };
+        snapshot.idle = read_states(&self.roots);
+        Ok(Some(snapshot))
+    }
+
+    fn is_live(&self) -> bool {
+        self.inner.is_live()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+
+    #[test]
+    fn test_read_states_and_residency() {
+        let fixture = Fixture::new();
+        for cpu in 0..2 {
+            for (idx, name, usage, time) in [
+                (0, "POLL", 10, 50),
+                (1, "C1", 100, 1000),
+                (10, "C10", 7, 400_000),
+            ] {
+                let dir = format!("sys/devices/system/cpu/cpu{cpu}/cpuidle/state{idx}");
+                fixture
+                    .file(&format!("{dir}/name"), format!("{name}\n"))
+                    .file(&format!("{dir}/usage"), format!("{usage}\n"))
+                    .file(&format!("{dir}/time"), format!("{time}\n"));
+            }
+        }
+        fixture.file("sys/devices/system/cpu/cpufreq/boost", "1\n");
+
+        let before = read_states(&fixture.roots());
+        let names: Vec<_> = before.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["POLL", "C1", "C10"]);
+        assert_eq!(before[2].time_us, 800_000);
+        assert_eq!(before[2].cpus, 2);
+
+        let mut after = before.clone();
+        after[2].time_us += 1_500_000;
+        after[2].usage += 30;
+
+        // 1.5s in C10 out of 2 CPUs * 1s
+        let residency = residency(&before, &after, 1.0);
+        assert_eq!(residency[2].residency, 75.0);
+        assert_eq!(residency[2].entry_rate, 30.0);
+        assert_eq!(residency[0].residency, 0.0);
+    }
+}
@@ -0,0 +1,324 @@
+//! Interrupt storm and stuck-source detection on sampled rates.
+//!
+//! Every i2c-hid report is read over the bus, so a HID interrupt is always
+//! followed by controller interrupts. A controller that keeps firing while
+//! all of its HID devices are silent is usually stuck retrying a transfer
+//! (bus held low, NAK loop); a HID GPIO that fires without controller
+//! activity is usually a floating or stuck interrupt line.
+
+use std::collections::HashMap;
+
+use serde::{Serialize, Serializer};
+
+use crate::discovery::InterruptSourceInfo;
+use crate::thresholds::Thresholds;
+
+/// Default time a condition must hold before it is reported, in seconds.
+pub const DEFAULT_MIN_DURATION_S: f64 = 5.0;
+
+/// Rate below which a source counts as silent, in irqs/s.
+const SILENT_RATE: f64 = 0.5;
+
+/// Controller rate that counts as activity for the stuck-bus check, in irqs/s.
+const STUCK_MIN_RATE: f64 = 10.0;
+
+/// HID rate that counts as activity for the spurious-GPIO check, in irqs/s.
+const SPURIOUS_MIN_RATE: f64 = 1.0;
+
+/// What a detection is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EventKind {
+    /// A source stayed above its warn level
+    Storm,
+    /// A controller kept firing while all its HID devices were silent
+    StuckBus,
+    /// A HID interrupt fired without activity on its controller
+    SpuriousGpio,
+}
+
+impl EventKind {
+    /// Identifier in event records and the exporter's `kind` label (e.g., "stuck_bus").
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EventKind::Storm => "storm",
+            EventKind::StuckBus => "stuck_bus",
+            EventKind::SpuriousGpio => "spurious_gpio",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            EventKind::Storm => "storm",
+            EventKind::StuckBus => "stuck bus",
+            EventKind::SpuriousGpio => "spurious GPIO",
+        }
+    }
+}
+
+impl Serialize for EventKind {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+/// Whether a condition started or ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    Start,
+    End,
+}
+
+/// One entry of the event log.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Event {
+    /// Seconds since sampling started
+    pub timestamp: f64,
+    pub kind: EventKind,
+    pub phase: Phase,
+    pub irq: String,
+    pub name: String,
+    /// Rate of the source in the interval that started or ended the condition
+    pub rate: f64,
+    /// Time the condition had held, in seconds
+    pub duration_s: f64,
+}
+
+impl Event {
+    /// One-line description (e.g., "storm: i2c_designware.5 (IRQ 21) at 4500.0 irqs/s for 5.0s").
+    pub fn describe(&self) -> String {
+        match self.phase {
+            Phase::Start => format!(
+                "{}: {} (IRQ {}) at {:.1} irqs/s for {:.1}s{}",
+                self.kind.label(),
+                self.name,
+                self.irq,
+                self.rate,
+                self.duration_s,
+                match self.kind {
+                    EventKind::Storm => "",
+                    EventKind::StuckBus => ", HID devices silent",
+                    EventKind::SpuriousGpio => ", controller silent",
+                }
+            ),
+            Phase::End => format!(
+                "{} ended: {} (IRQ {}) after {:.1}s",
+                self.kind.label(),
+                self.name,
+                self.irq,
+                self.duration_s
+            ),
+        }
+    }
+}
+
+/// A source as seen by the detector.
+struct Source {
+    irq: String,
+    name: String,
+    controller: String,
+    is_controller: bool,
+    /// Warn level of the source's limit, in irqs/s
+    threshold: f64,
+}
+
+/// Time a condition has held, and whether it has been reported.
+#[derive(Default)]
+struct Condition {
+    held_s: f64,
+    active: bool,
+}
+
+/// Tracks conditions across samples and turns them into events.
+pub struct Detector {
+    sources: Vec<Source>,
+    min_duration_s: f64,
+    conditions: HashMap<(EventKind, String), Condition>,
+}
+
+impl Detector {
+    pub fn new(
+        sources: &[InterruptSourceInfo],
+        thresholds: &Thresholds,
+        min_duration_s: f64,
+    ) -> Self {
+        Self {
+            sources: sources
+                .iter()
+                .map(|s| Source {
+                    irq: s.irq.clone(),
+                    name: s.name.clone(),
+                    controller: s.controller.clone(),
+                    is_controller: s.is_controller,
+                    threshold: thresholds.limit_for(s).warn,
+                })
+                .collect(),
+            min_duration_s,
+            conditions: HashMap::new(),
+        }
+    }
+
+    /// Feed the rates of one interval of `dt_s` seconds, keyed by IRQ.
+    ///
+    /// Sources missing from `rates` are left as they are. Intervals that
+    /// are not fed (e.g., across a suspend) do not count towards durations.
+    pub fn update(
+        &mut self,
+        timestamp: f64,
+        dt_s: f64,
+        rates: &HashMap<String, f64>,
+    ) -> Vec<Event> {
+        let mut checks = Vec::new();
+        for source in &self.sources {
+            let Some(&rate) = rates.get(&source.irq) else {
+                continue;
+            };
+            let storm = source.threshold > 0.0 && rate > source.threshold;
+            checks.push((EventKind::Storm, source, rate, storm));
+
+            // HID devices on the same controller, with a known rate
+            let peers: Vec<f64> = self
+                .sources
+                .iter()
+                .filter(|s| s.is_controller != source.is_controller)
+                .filter(|s| s.controller == source.controller)
+                .filter_map(|s| rates.get(&s.irq).copied())
+                .collect();
+            if peers.is_empty() {
+                continue;
+            }
+            let peers_silent = peers.iter().all(|&r| r < SILENT_RATE);
+            if source.is_controller {
+                let stuck = rate >= STUCK_MIN_RATE && peers_silent;
+                checks.push((EventKind::StuckBus, source, rate, stuck));
+            } else {
+                let spurious = rate >= SPURIOUS_MIN_RATE && peers_silent;
+                checks.push((EventKind::SpuriousGpio, source, rate, spurious));
+            }
+        }
+
+        let mut events = Vec::new();
+        for (kind, source, rate, holds) in checks {
+            let condition = self
+                .conditions
+                .entry((kind, source.irq.clone()))
+                .or_default();
+            let event = |phase, duration_s| Event {
+                timestamp,
+                kind,
+                phase,
+                irq: source.irq.clone(),
+                name: source.name.clone(),
+                rate,
+                duration_s,
+            };
+            if holds {
+                condition.held_s += dt_s;
+                if !condition.active && condition.held_s >= self.min_duration_s {
+                    condition.active = true;
+                    events.push(event(Phase::Start, condition.held_s));
+                }
+            } else {
+                if condition.active {
+                    events.push(event(Phase::End, condition.held_s));
+                }
+                *condition = Condition::default();
+            }
+        }
+        events
+    }
+
+    /// Conditions reported and not yet ended, as (kind, IRQ).
+    pub fn active(&self) -> impl Iterator<Item = (EventKind, &str)> {
+        self.conditions
+            .iter()
+            .filter(|(_, c)| c.active)
+            .map(|((kind, irq), _)| (*kind, irq.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::fixture;
+
+    fn rates(controller: f64, hid: f64) -> HashMap<String, f64> {
+        HashMap::from([("21".to_string(), controller), ("203".to_string(), hid)])
+    }
+
+    #[test]
+    fn test_storm_needs_min_duration() {
+        let mut detector =
+            Detector::new(&fixture::touchpad_sources(), &Thresholds::new(1000.0), 3.0);
+
+        // Normal touchpad use: 34 controller IRQs per report
+        assert!(detector.update(1.0, 1.0, &rates(850.0, 25.0)).is_empty());
+
+        // A 2s burst is not a storm
+        assert!(detector.update(2.0, 1.0, &rates(3400.0, 100.0)).is_empty());
+        assert!(detector.update(3.0, 1.0, &rates(3400.0, 100.0)).is_empty());
+        assert!(detector.update(4.0, 1.0, &rates(850.0, 25.0)).is_empty());
+
+        let mut events = Vec::new();
+        for t in 5..=7 {
+            events.extend(detector.update(t as f64, 1.0, &rates(3400.0, 100.0)));
+        }
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            (events[0].kind, events[0].phase),
+            (EventKind::Storm, Phase::Start)
+        );
+        assert_eq!(events[0].timestamp, 7.0);
+        assert_eq!(
+            events[0].describe(),
+            "storm: i2c_designware.5 (Touchpad) (IRQ 21) at 3400.0 irqs/s for 3.0s"
+        );
+        assert_eq!(detector.active().count(), 1);
+
+        let events = detector.update(8.0, 1.0, &rates(0.0, 0.0));
+        assert_eq!(events[0].phase, Phase::End);
+        assert_eq!(events[0].duration_s, 3.0);
+        assert_eq!(detector.active().count(), 0);
+    }
+
+    #[test]
+    fn test_stuck_bus_and_spurious_gpio() {
+        let mut detector = Detector::new(
+            &fixture::touchpad_sources(),
+            &Thresholds::new(10_000.0),
+            2.0,
+        );
+
+        // Controller firing with a silent touchpad
+        detector.update(1.0, 1.0, &rates(500.0, 0.0));
+        let events = detector.update(2.0, 1.0, &rates(500.0, 0.0));
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, EventKind::StuckBus);
+        assert_eq!(events[0].irq, "21");
+
+        // Touchpad GPIO firing with no bus reads
+        let mut events = detector.update(3.0, 1.0, &rates(0.0, 40.0));
+        events.extend(detector.update(4.0, 1.0, &rates(0.0, 40.0)));
+        let kinds: Vec<_> = events.iter().map(|e| (e.kind, e.phase)).collect();
+        assert_eq!(
+            kinds,
+            [
+                (EventKind::StuckBus, Phase::End),
+                (EventKind::SpuriousGpio, Phase::Start)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_event_kind_serializes_as_str() {
+        for kind in [
+            EventKind::Storm,
+            EventKind::StuckBus,
+            EventKind::SpuriousGpio,
+        ] {
+            assert_eq!(serde_json::to_value(kind).unwrap(), kind.as_str());
+        }
+    }
+}
@@ -0,0 +1,226 @@
+//! Device-tree nodes as exposed in /sys/firmware/devicetree/base.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// First interrupt of a device-tree node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OfInterrupt {
+    /// Node of the interrupt parent (e.g., a GPIO/pinctrl controller), if resolvable
+    pub parent: Option<PathBuf>,
+    /// Hardware IRQ number (the pin for GPIO controllers) within the parent,
+    /// if the specifier format of the parent is known
+    pub hwirq: Option<u32>,
+}
+
+/// Specifier type of a GIC shared peripheral interrupt.
+const GIC_SPI: u32 = 0;
+
+/// Specifier type of a GIC private peripheral interrupt.
+const GIC_PPI: u32 = 1;
+
+/// Decode a property of big-endian 32-bit cells.
+fn cells(content: &[u8]) -> Vec<u32> {
+    content
+        .chunks_exact(4)
+        .map(|c| u32::from_be_bytes([c[0], c[1], c[2], c[3]]))
+        .collect()
+}
+
+fn read_cells(node: &Path, property: &str) -> Option<Vec<u32>> {
+    fs::read(node.join(property)).ok().map(|c| cells(&c))
+}
+
+/// First entry of the node's compatible list (e.g., "hid-over-i2c").
+pub fn compatible(node: &Path) -> Option<String> {
+    let content = fs::read(node.join("compatible")).ok()?;
+    content
+        .split(|&b| b == 0)
+        .find(|s| !s.is_empty())
+        .map(|s| String::from_utf8_lossy(s).to_string())
+}
+
+/// Whether the node is an ARM GIC (e.g., "arm,gic-v3", "arm,cortex-a15-gic").
+fn is_gic(node: &Path) -> bool {
+    let Ok(content) = fs::read(node.join("compatible")) else {
+        return false;
+    };
+    content
+        .split(|&b| b == 0)
+        .map(String::from_utf8_lossy)
+        .any(|c| c.starts_with("arm,gic") || c.ends_with("-gic"))
+}
+
+/// Whether the node is a GPIO controller (has the `gpio-controller` property).
+pub fn is_gpio_controller(node: &Path) -> bool {
+    node.join("gpio-controller").exists()
+}
+
+/// Resolve the first interrupt of a node.
+///
+/// Handles `interrupts-extended` (parent phandle per entry) as well as
+/// `interrupts` with an `interrupt-parent` inherited from the closest
+/// ancestor that sets one. `base` is the devicetree root used to look up
+/// phandles.
+pub fn interrupt(node: &Path, base: &Path) -> Option<OfInterrupt> {
+    if let Some(cells) = read_cells(node, "interrupts-extended")
+        && cells.len() >= 2
+    {
+        let parent = find_phandle(base, cells[0]);
+        let hwirq = specifier_hwirq(parent.as_deref(), &cells[1..]);
+        return Some(OfInterrupt { parent, hwirq });
+    }
+
+    let specifier = read_cells(node, "interrupts").filter(|c| !c.is_empty())?;
+    let parent = node
+        .ancestors()
+        .take_while(|n| n.starts_with(base))
+        .find_map(|n| read_cells(n, "interrupt-parent")?.first().copied())
+        .and_then(|phandle| find_phandle(base, phandle));
+    let hwirq = specifier_hwirq(parent.as_deref(), &specifier);
+    Some(OfInterrupt { parent, hwirq })
+}
+
+/// Decode the hwirq from an interrupt specifier, according to the parent's
+/// `#interrupt-cells`.
+///
+/// One- and two-cell specifiers (GPIO and pinctrl controllers, wakeup
+/// controllers like the Qualcomm PDC) start with the hwirq. Three-cell GIC
+/// specifiers are `<type number flags>`, with SPIs numbered from 32 and PPIs
+/// from 16. Other three-cell formats are not decoded.
+fn specifier_hwirq(parent: Option<&Path>, specifier: &[u32]) -> Option<u32> {
+    let interrupt_cells = parent
+        .and_then(|p| read_cells(p, "#interrupt-cells"))
+        .and_then(|c| c.first().copied());
+    match interrupt_cells {
+        None | Some(1) | Some(2) => specifier.first().copied(),
+        Some(3) if parent.is_some_and(is_gic) => match specifier {
+            [GIC_SPI, number, ..] => Some(number + 32),
+            [GIC_PPI, number, ..] => Some(number + 16),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Find the node with the given phandle below `dir`.
+fn find_phandle(dir: &Path, phandle: u32) -> Option<PathBuf> {
+    if read_cells(dir, "phandle").and_then(|c| c.first().copied()) == Some(phandle) {
+        return Some(dir.to_path_buf());
+    }
+    fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+        .find_map(|e| find_phandle(&e.path(), phandle))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+
+    #[test]
+    fn test_interrupt_with_inherited_parent() {
+        let fixture = Fixture::new();
+        let base = "sys/firmware/devicetree/base";
+        fixture
+            .file(
+                &format!("{base}/soc@0/pinctrl@f100000/phandle"),
+                0x2au32.to_be_bytes(),
+            )
+            .file(&format!("{base}/soc@0/pinctrl@f100000/gpio-controller"), "")
+            .file(
+                &format!("{base}/soc@0/interrupt-parent"),
+                0x1u32.to_be_bytes(),
+            )
+            .file(
+                &format!("{base}/soc@0/i2c@a8c000/touchpad@15/interrupt-parent"),
+                0x2au32.to_be_bytes(),
+            )
+            .file(
+                &format!("{base}/soc@0/i2c@a8c000/touchpad@15/interrupts"),
+                [81u32, 8].map(u32::to_be_bytes).concat(),
+            )
+            .file(
+                &format!("{base}/soc@0/i2c@a8c000/touchpad@15/compatible"),
+                "elan,ekth3000\0hid-over-i2c\0",
+            );
+
+        let base = fixture.roots().sys_path("firmware/devicetree/base");
+        let node = base.join("soc@0/i2c@a8c000/touchpad@15");
+        let irq = interrupt(&node, &base).unwrap();
+        assert_eq!(irq.hwirq, Some(81));
+        let parent = irq.parent.unwrap();
+        assert!(parent.ends_with("pinctrl@f100000"));
+        assert!(is_gpio_controller(&parent));
+        assert_eq!(compatible(&node).as_deref(), Some("elan,ekth3000"));
+    }
+
+    #[test]
+    fn test_interrupt_with_three_cell_parent() {
+        let fixture = Fixture::new();
+        let base = "sys/firmware/devicetree/base";
+        fixture
+            .file(
+                &format!("{base}/soc@0/interrupt-controller@17a00000/phandle"),
+                0x1u32.to_be_bytes(),
+            )
+            .file(
+                &format!("{base}/soc@0/interrupt-controller@17a00000/#interrupt-cells"),
+                3u32.to_be_bytes(),
+            )
+            .file(
+                &format!("{base}/soc@0/interrupt-controller@17a00000/compatible"),
+                "arm,gic-v3\0",
+            )
+            .file(
+                &format!("{base}/soc@0/intc@10200620/phandle"),
+                0x2u32.to_be_bytes(),
+            )
+            .file(
+                &format!("{base}/soc@0/intc@10200620/#interrupt-cells"),
+                3u32.to_be_bytes(),
+            )
+            .file(
+                &format!("{base}/soc@0/intc@10200620/compatible"),
+                "vendor,wakeup-intc\0",
+            )
+            .file(
+                &format!("{base}/soc@0/interrupt-parent"),
+                0x1u32.to_be_bytes(),
+            )
+            // <GIC_SPI 81 IRQ_TYPE_LEVEL_HIGH>
+            .file(
+                &format!("{base}/soc@0/i2c@a8c000/touchpad@15/interrupts"),
+                [0u32, 81, 4].map(u32::to_be_bytes).concat(),
+            )
+            // <GIC_PPI 7 IRQ_TYPE_LEVEL_HIGH>
+            .file(
+                &format!("{base}/soc@0/i2c@a8c000/keyboard@3a/interrupts"),
+                [1u32, 7, 4].map(u32::to_be_bytes).concat(),
+            )
+            .file(
+                &format!("{base}/soc@0/i2c@a8c000/touchscreen@5d/interrupts-extended"),
+                [2u32, 0, 11, 4].map(u32::to_be_bytes).concat(),
+            );
+
+        let base = fixture.roots().sys_path("firmware/devicetree/base");
+        let irq = |node: &str| interrupt(&base.join(node), &base).unwrap();
+
+        let touchpad = irq("soc@0/i2c@a8c000/touchpad@15");
+        assert_eq!(touchpad.hwirq, Some(113));
+        assert!(
+            touchpad
+                .parent
+                .unwrap()
+                .ends_with("interrupt-controller@17a00000")
+        );
+        assert_eq!(irq("soc@0/i2c@a8c000/keyboard@3a").hwirq, Some(23));
+
+        // Unknown three-cell format
+        let touchscreen = irq("soc@0/i2c@a8c000/touchscreen@5d");
+        assert_eq!(touchscreen.hwirq, None);
+        assert!(touchscreen.parent.unwrap().ends_with("intc@10200620"));
+    }
+}
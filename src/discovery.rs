@@ -2,16 +2,27 @@
 
 use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-use anyhow::{Context, Result};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::devicetree;
+use crate::hid_descriptor::{self, HidCollection, ReportDescriptor};
+use crate::interrupts::{self, InterruptSource};
+use crate::power::PowerState;
+use crate::roots::FsRoots;
+use crate::sysirq::{self, IrqDesc};
 
 /// Information about an I2C HID device discovered from sysfs.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[allow(dead_code)]
 pub struct HidDevice {
-    /// ACPI device name (e.g., "PIXA3854:00")
+    /// ACPI device name (e.g., "PIXA3854:00"), or the compatible string on device-tree systems
     pub acpi_name: String,
+    /// I2C client device name (e.g., "i2c-PIXA3854:00", or "2-0015" on device-tree systems)
+    #[serde(default)]
+    pub client: String,
     /// Vendor ID
     pub vendor_id: u16,
     /// Product ID
@@ -24,14 +35,38 @@ pub struct HidDevice {
     pub bus_num: u8,
     /// I2C controller name (e.g., "i2c_designware.5")
     pub controller: String,
-    /// GPIO IRQ number for this device (from /proc/interrupts)
+    /// IRQ number for this device (usually a GPIO interrupt)
     pub gpio_irq: Option<String>,
     /// Input device names (e.g., ["Touchpad", "Mouse"])
     pub input_names: Vec<String>,
+    /// Top-level application collections of the HID report descriptor
+    #[serde(default)]
+    pub collections: Vec<HidCollection>,
+    /// Size of the largest input report in bytes (including the report ID)
+    #[serde(default)]
+    pub max_input_report: Option<usize>,
+    /// Runtime PM and wakeup state of the I2C client at discovery
+    #[serde(default)]
+    pub power: Option<PowerState>,
+    /// Sysfs directory of the I2C client (not recorded)
+    #[serde(skip)]
+    pub sysfs_dir: Option<PathBuf>,
+}
+
+impl HidDevice {
+    /// Name for display, with the client address on device-tree systems
+    /// where several devices may share a compatible string.
+    pub fn display_name(&self) -> String {
+        if self.client.is_empty() || self.client.starts_with("i2c-") {
+            self.acpi_name.clone()
+        } else {
+            format!("{} ({})", self.acpi_name, self.client)
+        }
+    }
 }
 
 /// Information about an I2C controller.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct I2cController {
     /// Controller name (e.g., "i2c_designware.5")
     pub name: String,
@@ -39,19 +74,50 @@ pub struct I2cController {
     pub bus_num: u8,
     /// IRQ number from /proc/interrupts
     pub irq: Option<String>,
+    /// Runtime PM and wakeup state of the controller device at discovery
+    #[serde(default)]
+    pub power: Option<PowerState>,
+    /// Sysfs directory of the controller device (not recorded)
+    #[serde(skip)]
+    pub sysfs_dir: Option<PathBuf>,
     /// HID devices attached to this controller
     pub hid_devices: Vec<HidDevice>,
 }
 
+/// Interrupt chip details of an IRQ line, as shown in /proc/interrupts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IrqChipInfo {
+    /// Interrupt chip name (e.g., "intel-gpio", "IR-IO-APIC")
+    pub chip: String,
+    /// Hardware IRQ number within the chip (the pin number for GPIO chips)
+    pub hwirq: Option<String>,
+    /// Trigger or flow type (e.g., "edge", "level", "fasteoi")
+    pub trigger: Option<String>,
+}
+
+impl IrqChipInfo {
+    /// Format like /proc/interrupts (e.g., "intel-gpio 18-edge").
+    pub fn describe(&self) -> String {
+        match (&self.hwirq, &self.trigger) {
+            (Some(hwirq), Some(trigger)) => format!("{} {}-{}", self.chip, hwirq, trigger),
+            (Some(hwirq), None) => format!("{} {}", self.chip, hwirq),
+            (None, Some(trigger)) => format!("{} {}", self.chip, trigger),
+            (None, None) => self.chip.clone(),
+        }
+    }
+}
+
 /// Discovered I2C interrupt topology.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct I2cTopology {
     /// Controllers with their attached HID devices
     pub controllers: Vec<I2cController>,
-    /// Map of ACPI name to GPIO IRQ number
+    /// Map of GPIO IRQ action names to IRQ numbers, guessed from /proc/interrupts
     pub gpio_irqs: HashMap<String, String>,
     /// Map of controller name to controller IRQ number
     pub controller_irqs: HashMap<String, String>,
+    /// Map of IRQ number to its interrupt chip details
+    pub irq_chips: HashMap<String, IrqChipInfo>,
 }
 
 impl I2cTopology {
@@ -73,10 +139,19 @@ impl I2cTopology {
                     format!(" ({})", names.join(", "))
                 };
 
+                let chip = self.irq_chips.get(irq);
                 sources.push(InterruptSourceInfo {
                     irq: irq.clone(),
                     name: format!("{}{}", controller.name, device_summary),
                     device_type: "I2C Controller".to_string(),
+                    chip: chip.map(|c| c.chip.clone()),
+                    trigger: chip.and_then(|c| c.trigger.clone()),
+                    controller: controller.name.clone(),
+                    device: controller.name.clone(),
+                    acpi_name: None,
+                    vendor_id: None,
+                    product_id: None,
+                    max_input_report: None,
                     is_controller: true,
                     parent_controller: None,
                     indent_level: 0,
@@ -86,10 +161,19 @@ impl I2cTopology {
             // Add each HID device under the controller
             for device in &controller.hid_devices {
                 if let Some(irq) = &device.gpio_irq {
+                    let chip = self.irq_chips.get(irq);
                     sources.push(InterruptSourceInfo {
                         irq: irq.clone(),
-                        name: device.acpi_name.clone(),
+                        name: device.display_name(),
                         device_type: device.device_type.clone(),
+                        chip: chip.map(|c| c.chip.clone()),
+                        trigger: chip.and_then(|c| c.trigger.clone()),
+                        controller: controller.name.clone(),
+                        device: device.client.clone(),
+                        acpi_name: Some(device.acpi_name.clone()),
+                        vendor_id: Some(device.vendor_id),
+                        product_id: Some(device.product_id),
+                        max_input_report: device.max_input_report,
                         is_controller: false,
                         parent_controller: Some(controller.name.clone()),
                         indent_level: 1,
@@ -112,6 +196,22 @@ pub struct InterruptSourceInfo {
     pub name: String,
     /// Device type (e.g., "Touchpad", "I2C Controller")
     pub device_type: String,
+    /// Interrupt chip name (e.g., "intel-gpio")
+    pub chip: Option<String>,
+    /// Trigger or flow type (e.g., "edge", "fasteoi")
+    pub trigger: Option<String>,
+    /// Controller name (the controller itself, or the one a HID device is attached to)
+    pub controller: String,
+    /// Sysfs device name that power state is keyed by (controller name or I2C client)
+    pub device: String,
+    /// ACPI name of the HID device (None for controllers)
+    pub acpi_name: Option<String>,
+    /// USB-style vendor ID of the HID device (None for controllers)
+    pub vendor_id: Option<u16>,
+    /// Product ID of the HID device (None for controllers)
+    pub product_id: Option<u16>,
+    /// Size of the largest input report in bytes (None for controllers or if unknown)
+    pub max_input_report: Option<usize>,
     /// Whether this is a controller (vs a HID device)
     pub is_controller: bool,
     /// Parent controller name (for HID devices)
@@ -120,96 +220,141 @@ pub struct InterruptSourceInfo {
     pub indent_level: u8,
 }
 
-/// Discover the I2C HID topology from sysfs and /proc/interrupts.
-pub fn discover() -> Result<I2cTopology> {
+/// I2C HID drivers whose devices are discovered (ACPI and device tree).
+const HID_DRIVERS: &[&str] = &[
+    "i2c_hid_acpi",
+    "i2c_hid_of",
+    "i2c_hid_of_goodix",
+    "i2c_hid_of_elan",
+];
+
+/// Discover the I2C HID topology from sysfs and /proc/interrupts below the given roots.
+pub fn discover(roots: &FsRoots) -> Result<I2cTopology> {
     let mut topology = I2cTopology {
         controllers: Vec::new(),
         gpio_irqs: HashMap::new(),
         controller_irqs: HashMap::new(),
+        irq_chips: HashMap::new(),
     };
 
-    // Parse /proc/interrupts to find GPIO and controller IRQs
-    let interrupts =
-        fs::read_to_string("/proc/interrupts").context("failed to read /proc/interrupts")?;
-
-    for line in interrupts.lines() {
-        let line = line.trim();
-        if line.is_empty() {
-            continue;
-        }
-
-        // Extract IRQ number
-        let Some(irq) = line.split(':').next() else {
-            continue;
-        };
-        let irq = irq.trim();
-
-        // Check for GPIO interrupts (intel-gpio, pinctrl-*)
-        if line.contains("intel-gpio") || line.contains("pinctrl") {
-            // Format: "200:  ... intel-gpio   33  FRMW0005:00"
-            // The ACPI name is typically at the end
-            if let Some(acpi_name) = extract_acpi_name(line) {
-                topology.gpio_irqs.insert(acpi_name, irq.to_string());
-            }
-        }
-
-        // Check for I2C controller interrupts
-        if line.contains("i2c_designware") {
-            // Format: "20:  ... i2c_designware.4"
-            // May also have "idma64.N, i2c_designware.N"
-            for part in line.split(&[' ', ','][..]) {
-                if part.contains("i2c_designware") {
-                    let controller_name = part.trim();
-                    topology
-                        .controller_irqs
-                        .insert(controller_name.to_string(), irq.to_string());
-                }
-            }
-        }
+    // IRQ descriptors from /sys/kernel/irq, or from /proc/interrupts where unavailable
+    let interrupts = interrupts::read_interrupts(roots)?;
+    let mut irqs = sysirq::read_irqs(roots);
+    if irqs.is_empty() {
+        irqs = interrupts.iter().map(IrqDesc::from).collect();
     }
 
+    // Guess GPIO IRQs from /proc/interrupts, as a fallback for exact matching
+    collect_irqs(&interrupts, &mut topology);
+
     // Discover I2C controllers from sysfs
     let mut controllers: HashMap<String, I2cController> = HashMap::new();
 
-    // Find I2C HID devices
-    let hid_driver_path = Path::new("/sys/bus/i2c/drivers/i2c_hid_acpi");
-    if hid_driver_path.exists() {
-        for entry in fs::read_dir(hid_driver_path)? {
+    let dt_base = fs::canonicalize(roots.sys_path("firmware/devicetree/base")).ok();
+
+    // Find I2C HID devices bound to the ACPI or device-tree drivers
+    for driver in HID_DRIVERS {
+        let Ok(entries) = fs::read_dir(roots.sys_path(&format!("bus/i2c/drivers/{}", driver)))
+        else {
+            continue;
+        };
+        for entry in entries {
             let entry = entry?;
-            let name = entry.file_name().to_string_lossy().to_string();
+            let client = entry.file_name().to_string_lossy().to_string();
 
-            // Skip non-device entries
-            if !name.starts_with("i2c-") {
+            // Skip non-device entries (bind, unbind, module, ...)
+            if !is_client(&client) {
                 continue;
             }
 
-            // Get ACPI name (strip "i2c-" prefix)
-            let acpi_name = name.strip_prefix("i2c-").unwrap_or(&name).to_string();
-
             // Follow symlink to find controller
             let link_path = entry.path();
             let real_path = fs::read_link(&link_path).unwrap_or_default();
             let real_path_str = real_path.to_string_lossy();
+            let client_dir = fs::canonicalize(&link_path).ok();
+
+            // ACPI clients are named after their ACPI device ("i2c-PIXA3854:00"),
+            // device-tree clients after their bus and address ("2-0015")
+            let (acpi_name, gpio_irq) = match client.strip_prefix("i2c-") {
+                Some(acpi_name) => {
+                    // i2c-hid requests its IRQ under the client name, which is the ACPI name
+                    let client_name = client_dir
+                        .as_deref()
+                        .and_then(|dir| read_trimmed(&dir.join("name")))
+                        .unwrap_or_else(|| acpi_name.to_string());
+                    let irq = resolve_client_irq(&client_name, &irqs)
+                        .or_else(|| topology.gpio_irqs.get(acpi_name).cloned());
+                    if let Some(irq) = &irq {
+                        record_irq_chip(irq, &interrupts, &irqs, &mut topology);
+                    }
+                    (acpi_name.to_string(), irq)
+                }
+                None => {
+                    let Some(client_dir) = &client_dir else {
+                        continue;
+                    };
+                    let of_node = fs::canonicalize(client_dir.join("of_node")).ok();
+                    let compatible = of_node
+                        .as_deref()
+                        .and_then(devicetree::compatible)
+                        .or_else(|| read_trimmed(&client_dir.join("name")))
+                        .unwrap_or_else(|| client.clone());
+                    let irq =
+                        resolve_of_irq(client_dir, of_node.as_deref(), dt_base.as_deref(), &irqs);
+                    if let Some(irq) = &irq {
+                        record_irq_chip(irq, &interrupts, &irqs, &mut topology);
+                    }
+                    (compatible, irq)
+                }
+            };
 
-            // Extract controller name (e.g., "i2c_designware.5")
+            // The controller is the parent device of the i2c-N adapter (e.g., "i2c_designware.5")
             let controller_name = extract_controller_name(&real_path_str);
 
             // Extract bus number (e.g., "i2c-5" -> 5)
             let bus_num = extract_bus_num(&real_path_str);
 
             // Get HID device info
-            let hid_device = discover_hid_device(&acpi_name, &controller_name, bus_num, &topology)?;
+            let mut hid_device = discover_hid_device(
+                &client,
+                &acpi_name,
+                gpio_irq,
+                &controller_name,
+                bus_num,
+                roots,
+            )?;
+            hid_device.power = client_dir.as_deref().and_then(PowerState::read);
+            hid_device.sysfs_dir = client_dir.clone();
+
+            if !controllers.contains_key(&controller_name) {
+                let controller_dir = client_dir
+                    .as_deref()
+                    .and_then(|client| Some(client.parent()?.parent()?.to_path_buf()));
+                let irq =
+                    resolve_controller_irq(&controller_name, controller_dir.as_deref(), &irqs);
+                if let Some(irq) = &irq {
+                    topology
+                        .controller_irqs
+                        .insert(controller_name.clone(), irq.clone());
+                    record_irq_chip(irq, &interrupts, &irqs, &mut topology);
+                }
+                controllers.insert(
+                    controller_name.clone(),
+                    I2cController {
+                        name: controller_name.clone(),
+                        bus_num,
+                        irq,
+                        power: controller_dir.as_deref().and_then(PowerState::read),
+                        sysfs_dir: controller_dir,
+                        hid_devices: Vec::new(),
+                    },
+                );
+            }
 
             // Add to controller
-            let controller = controllers
-                .entry(controller_name.clone())
-                .or_insert_with(|| I2cController {
-                    name: controller_name.clone(),
-                    bus_num,
-                    irq: topology.controller_irqs.get(&controller_name).cloned(),
-                    hid_devices: Vec::new(),
-                });
-            controller.hid_devices.push(hid_device);
+            if let Some(controller) = controllers.get_mut(&controller_name) {
+                controller.hid_devices.push(hid_device);
+            }
         }
     }
 
@@ -222,30 +367,195 @@ pub fn discover() -> Result<I2cTopology> {
     Ok(topology)
 }
 
-/// Extract ACPI device name from an interrupt line.
-fn extract_acpi_name(line: &str) -> Option<String> {
-    // Look for patterns like "PIXA3854:00", "FRMW0004:00", "CSW1322:00"
-    for part in line.split_whitespace().rev() {
-        if part.contains(':')
-            && part.chars().next().is_some_and(|c| c.is_ascii_uppercase())
-            && !part.contains("IR-")
-            && !part.contains("PCI-")
-        {
-            return Some(part.to_string());
+/// Guess GPIO IRQs, and record their chip details, from parsed interrupts.
+///
+/// Any action on a GPIO chip is taken as a device name. This is only a
+/// fallback for devices whose client name matches no IRQ action exactly.
+fn collect_irqs(interrupts: &[InterruptSource], topology: &mut I2cTopology) {
+    for source in interrupts {
+        let Some(chip) = &source.chip else {
+            continue;
+        };
+
+        // GPIO interrupts (intel-gpio, amd_gpio, pinctrl-*) carry the device name as action
+        if !is_gpio_chip(chip) || source.actions.is_empty() {
+            continue;
+        }
+
+        for action in &source.actions {
+            topology
+                .gpio_irqs
+                .insert(action.clone(), source.irq.clone());
         }
+        record_irq_chip(&source.irq, interrupts, &[], topology);
+    }
+}
+
+/// Whether an interrupt chip is a GPIO controller (intel-gpio, amd_gpio, pinctrl-*, mt-eint).
+fn is_gpio_chip(chip: &str) -> bool {
+    chip.contains("gpio") || chip.contains("pinctrl") || chip.contains("eint")
+}
+
+/// Whether a driver directory entry is an I2C client ("i2c-ACPI0001:00" or "2-0015").
+fn is_client(name: &str) -> bool {
+    if name.starts_with("i2c-") {
+        return true;
+    }
+    name.split_once('-').is_some_and(|(bus, addr)| {
+        !bus.is_empty()
+            && bus.bytes().all(|b| b.is_ascii_digit())
+            && addr.len() == 4
+            && addr.bytes().all(|b| b.is_ascii_hexdigit())
+    })
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+/// Find the IRQ requested under an I2C client's name.
+///
+/// If several IRQs carry the name, prefer one on a GPIO chip.
+fn resolve_client_irq(client_name: &str, irqs: &[IrqDesc]) -> Option<String> {
+    let candidates: Vec<_> = irqs.iter().filter(|d| d.has_action(client_name)).collect();
+    candidates
+        .iter()
+        .find(|d| d.chip.as_deref().is_some_and(is_gpio_chip))
+        .or(candidates.first())
+        .map(|d| d.irq.clone())
+}
+
+/// Find the IRQ of a device-tree I2C client.
+///
+/// The OF node gives the hwirq (pin) within the interrupt parent. Among the
+/// IRQs with that hwirq, prefer the one whose action is the client name
+/// (i2c-hid requests its IRQ under it), then a unique one on a GPIO chip if
+/// the parent is a GPIO controller. Without an OF interrupt, or when its
+/// specifier cannot be decoded or matches no IRQ, fall back to a unique
+/// action match.
+fn resolve_of_irq(
+    client_dir: &Path,
+    of_node: Option<&Path>,
+    dt_base: Option<&Path>,
+    irqs: &[IrqDesc],
+) -> Option<String> {
+    let client_name = read_trimmed(&client_dir.join("name"));
+    let named = |d: &&IrqDesc| client_name.as_ref().is_some_and(|n| d.has_action(n));
+    let unique =
+        |candidates: Vec<&IrqDesc>| (candidates.len() == 1).then(|| candidates[0].irq.clone());
+    let by_name = || unique(irqs.iter().filter(named).collect());
+
+    let of_irq = of_node
+        .zip(dt_base)
+        .and_then(|(node, base)| devicetree::interrupt(node, base));
+    let Some((of_irq, hwirq)) = of_irq.and_then(|i| i.hwirq.map(|hwirq| (i, hwirq))) else {
+        return by_name();
+    };
+
+    let hwirq = hwirq.to_string();
+    let candidates: Vec<_> = irqs
+        .iter()
+        .filter(|d| d.hwirq.as_deref() == Some(hwirq.as_str()))
+        .collect();
+    if let Some(desc) = candidates.iter().find(|d| named(d)) {
+        return Some(desc.irq.clone());
     }
-    None
+
+    let gpio_parent = of_irq
+        .parent
+        .as_deref()
+        .is_some_and(devicetree::is_gpio_controller);
+    unique(
+        candidates
+            .into_iter()
+            .filter(|d| !gpio_parent || d.chip.as_deref().is_some_and(is_gpio_chip))
+            .collect(),
+    )
+    .or_else(by_name)
+}
+
+/// Record the chip details of an IRQ.
+///
+/// /proc/interrupts is preferred since it shows the flow handler (e.g.,
+/// "fasteoi"); the sysfs descriptor covers IRQs missing from it.
+fn record_irq_chip(
+    irq: &str,
+    interrupts: &[InterruptSource],
+    irqs: &[IrqDesc],
+    topology: &mut I2cTopology,
+) {
+    let desc = interrupts
+        .iter()
+        .find(|s| s.irq == irq && s.chip.is_some())
+        .map(IrqDesc::from)
+        .or_else(|| irqs.iter().find(|d| d.irq == irq).cloned());
+    let Some(IrqDesc {
+        chip: Some(chip),
+        hwirq,
+        trigger,
+        ..
+    }) = desc
+    else {
+        return;
+    };
+    topology.irq_chips.insert(
+        irq.to_string(),
+        IrqChipInfo {
+            chip,
+            hwirq,
+            trigger,
+        },
+    );
+}
+
+/// Find the IRQ of an I2C controller, whatever its driver.
+///
+/// Most drivers name their IRQ action after the device (e.g.,
+/// "i2c_designware.5", possibly shared with "idma64.5"), so try an exact
+/// action match first. Drivers that use a fixed name (e.g., i801's
+/// "i801_smbus") are found through the device's `msi_irqs/` directory or its
+/// `irq` file.
+fn resolve_controller_irq(name: &str, dir: Option<&Path>, irqs: &[IrqDesc]) -> Option<String> {
+    if let Some(irq) = sysirq::find_irq_by_action(irqs, name) {
+        return Some(irq);
+    }
+
+    let dir = dir?;
+    let msi_irq = fs::read_dir(dir.join("msi_irqs"))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|e| e.file_name().to_str()?.parse::<u32>().ok())
+        .min();
+    if let Some(irq) = msi_irq {
+        return Some(irq.to_string());
+    }
+
+    fs::read_to_string(dir.join("irq"))
+        .ok()
+        .and_then(|irq| irq.trim().parse::<u32>().ok())
+        .filter(|&irq| irq != 0)
+        .map(|irq| irq.to_string())
 }
 
 /// Extract controller name from sysfs path.
+///
+/// The controller is the device directly above the "i2c-N" adapter, e.g.
+/// "i2c_designware.5" or the PCI address of an i801 SMBus controller.
 fn extract_controller_name(path: &str) -> String {
-    // Look for "i2c_designware.N" in the path
-    for part in path.split('/') {
-        if part.starts_with("i2c_designware.") {
-            return part.to_string();
-        }
-    }
-    "unknown".to_string()
+    let parts: Vec<_> = path.split('/').collect();
+    parts
+        .windows(2)
+        .rev()
+        .find(|pair| is_adapter(pair[1]))
+        .map(|pair| pair[0].to_string())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Whether a sysfs path component is an I2C adapter ("i2c-N").
+fn is_adapter(part: &str) -> bool {
+    part.strip_prefix("i2c-")
+        .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
 }
 
 /// Extract bus number from sysfs path.
@@ -263,27 +573,35 @@ fn extract_bus_num(path: &str) -> u8 {
 
 /// Discover details about a specific HID device.
 fn discover_hid_device(
+    client: &str,
     acpi_name: &str,
+    gpio_irq: Option<String>,
     controller: &str,
     bus_num: u8,
-    topology: &I2cTopology,
+    roots: &FsRoots,
 ) -> Result<HidDevice> {
     let mut device = HidDevice {
         acpi_name: acpi_name.to_string(),
+        client: client.to_string(),
         vendor_id: 0,
         product_id: 0,
         device_type: "Unknown".to_string(),
         driver: String::new(),
         bus_num,
         controller: controller.to_string(),
-        gpio_irq: topology.gpio_irqs.get(acpi_name).cloned(),
+        gpio_irq,
         input_names: Vec::new(),
+        collections: Vec::new(),
+        max_input_report: None,
+        power: None,
+        sysfs_dir: None,
     };
+    let mut descriptor = ReportDescriptor::default();
 
     // Find HID device in /sys/bus/hid/devices/
-    let hid_devices_path = Path::new("/sys/bus/hid/devices");
+    let hid_devices_path = roots.sys_path("bus/hid/devices");
     if hid_devices_path.exists() {
-        for entry in fs::read_dir(hid_devices_path)? {
+        for entry in fs::read_dir(&hid_devices_path)? {
             let entry = entry?;
             let name = entry.file_name().to_string_lossy().to_string();
 
@@ -292,10 +610,13 @@ fn discover_hid_device(
                 continue;
             }
 
-            // Check if this HID device matches our ACPI device
+            // Check if this HID device belongs to our I2C client (HID_PHYS is its device name)
             let uevent_path = entry.path().join("uevent");
             if let Ok(uevent) = fs::read_to_string(&uevent_path) {
-                if !uevent.contains(acpi_name) {
+                if !uevent
+                    .lines()
+                    .any(|line| line.strip_prefix("HID_PHYS=") == Some(client))
+                {
                     continue;
                 }
 
@@ -326,18 +647,28 @@ fn discover_hid_device(
                     }
                 }
 
+                // Parse the report descriptor for collections and report sizes
+                if let Ok(content) = fs::read(entry.path().join("report_descriptor")) {
+                    descriptor = hid_descriptor::parse(&content);
+                    device.collections = descriptor.collections.clone();
+                    device.max_input_report = descriptor.max_input_report;
+                }
+
                 break;
             }
         }
     }
 
-    // Determine device type from driver and input names
-    device.device_type = determine_device_type(&device);
+    // Determine device type from the report descriptor, then driver and input names
+    device.device_type = match descriptor.device_type() {
+        Some(device_type) => device_type.to_string(),
+        None => determine_device_type(&device),
+    };
 
     Ok(device)
 }
 
-/// Determine a human-readable device type.
+/// Guess a human-readable device type without a usable report descriptor.
 fn determine_device_type(device: &HidDevice) -> String {
     // Check input names first
     for name in &device.input_names {
@@ -358,15 +689,8 @@ fn determine_device_type(device: &HidDevice) -> String {
 
     // Check driver
     match device.driver.as_str() {
-        "hid-multitouch" => {
-            // Could be touchpad or touchscreen
-            // PixArt (093A) is typically touchpad
-            // Wacom and others often touchscreen
-            if device.vendor_id == 0x093A {
-                return "Touchpad".to_string();
-            }
-            "Touchscreen".to_string()
-        }
+        // Could be a touchpad or a touchscreen; only the descriptor can tell
+        "hid-multitouch" => "Touch Device".to_string(),
         "hid-sensor-hub" => "Sensor Hub".to_string(),
         "hid-generic" => {
             // Check for specific input types
@@ -384,14 +708,413 @@ fn determine_device_type(device: &HidDevice) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::fixture::Fixture;
+
+    #[test]
+    fn test_collect_irqs() {
+        let content = r#"           CPU0       CPU1       CPU2       CPU3
+  0:         23          0          0          0   IO-APIC   2-edge      timer
+ 28:        300          0          0          0  IR-IO-APIC   28-fasteoi   idma64.1, i2c_designware.1
+200:          0       1306          0          0  intel-gpio   33  FRMW0005:00
+203:          0          0          0      21323  intel-gpio   18-edge  PIXA3854:00
+"#;
+        let interrupts = interrupts::parse_interrupts(content).unwrap();
+        let mut topology = I2cTopology {
+            controllers: Vec::new(),
+            gpio_irqs: HashMap::new(),
+            controller_irqs: HashMap::new(),
+            irq_chips: HashMap::new(),
+        };
+        collect_irqs(&interrupts, &mut topology);
+
+        assert_eq!(topology.gpio_irqs.get("PIXA3854:00").unwrap(), "203");
+        assert_eq!(topology.gpio_irqs.get("FRMW0005:00").unwrap(), "200");
+        assert!(!topology.gpio_irqs.contains_key("i2c_designware.1"));
+        assert!(!topology.irq_chips.contains_key("0"));
+        assert_eq!(topology.irq_chips["203"].describe(), "intel-gpio 18-edge");
+
+        // Controllers are matched by exact action name, even on a shared IRQ
+        let irqs: Vec<_> = interrupts.iter().map(IrqDesc::from).collect();
+        assert_eq!(
+            resolve_controller_irq("i2c_designware.1", None, &irqs).as_deref(),
+            Some("28")
+        );
+        assert_eq!(resolve_controller_irq("i2c_designware", None, &irqs), None);
+    }
+
+    #[test]
+    fn test_discover_from_fixture() {
+        let fixture = Fixture::new();
+        fixture
+            .file(
+                "proc/interrupts",
+                "           CPU0       CPU1
+ 21:       4000          0  IR-IO-APIC   21-fasteoi   idma64.5, i2c_designware.5
+203:          0        137  intel-gpio   18-level  PIXA3854:00
+",
+            )
+            .file(
+                "sys/devices/pci0000:00/0000:00:19.1/i2c_designware.5/i2c-5/i2c-PIXA3854:00/name",
+                "PIXA3854:00\n",
+            )
+            .file(
+                "sys/devices/pci0000:00/0000:00:19.1/i2c_designware.5/power/runtime_status",
+                "active\n",
+            )
+            .file(
+                "sys/devices/pci0000:00/0000:00:19.1/i2c_designware.5/i2c-5/i2c-PIXA3854:00/power/wakeup",
+                "enabled\n",
+            )
+            .symlink(
+                "sys/bus/i2c/drivers/i2c_hid_acpi/i2c-PIXA3854:00",
+                "../../../../devices/pci0000:00/0000:00:19.1/i2c_designware.5/i2c-5/i2c-PIXA3854:00",
+            )
+            .file(
+                "sys/bus/hid/devices/0018:093A:0274.0001/uevent",
+                "DRIVER=hid-multitouch\nHID_PHYS=i2c-PIXA3854:00\n",
+            )
+            .file(
+                "sys/bus/hid/devices/0018:093A:0274.0001/input/input5/name",
+                "PIXA3854:00 093A:0274 Touchpad\n",
+            );
+
+        let topology = discover(&fixture.roots()).unwrap();
+        assert_eq!(topology.controllers.len(), 1);
+
+        let controller = &topology.controllers[0];
+        assert_eq!(controller.name, "i2c_designware.5");
+        assert_eq!(controller.bus_num, 5);
+        assert_eq!(controller.irq.as_deref(), Some("21"));
+
+        let device = &controller.hid_devices[0];
+        assert_eq!(device.acpi_name, "PIXA3854:00");
+        assert_eq!(device.vendor_id, 0x093A);
+        assert_eq!(device.product_id, 0x0274);
+        assert_eq!(device.driver, "hid-multitouch");
+        assert_eq!(device.device_type, "Touchpad");
+        assert_eq!(device.gpio_irq.as_deref(), Some("203"));
+        assert_eq!(topology.irq_chips["21"].describe(), "IR-IO-APIC 21-fasteoi");
+
+        let power = controller.power.as_ref().unwrap();
+        assert_eq!(power.runtime_status.as_deref(), Some("active"));
+        let power = device.power.as_ref().unwrap();
+        assert_eq!(power.wakeup.as_deref(), Some("enabled"));
+        assert!(
+            device
+                .sysfs_dir
+                .as_ref()
+                .unwrap()
+                .ends_with("i2c-PIXA3854:00")
+        );
+    }
 
     #[test]
-    fn test_extract_acpi_name() {
-        let line = " 203:          0          0          0      21323 intel-gpio   18  PIXA3854:00";
-        assert_eq!(extract_acpi_name(line), Some("PIXA3854:00".to_string()));
+    fn test_discover_non_designware_controllers() {
+        let fixture = Fixture::new();
+        fixture
+            .file(
+                "proc/interrupts",
+                "           CPU0
+ 16:         42  IR-IO-APIC   16-fasteoi   i801_smbus
+ 60:        900  amd_gpio   8-level  ELAN0001:00
+ 61:       1800  IR-PCI-MSI   2097152-edge   AMDI0011:00
+",
+            )
+            // i801 names its IRQ "i801_smbus", so the PCI device's irq file is used
+            .file(
+                "sys/devices/pci0000:00/0000:00:1f.4/irq",
+                "16
+",
+            )
+            .file(
+                "sys/devices/pci0000:00/0000:00:1f.4/i2c-0/i2c-SYNA0001:00/name",
+                "SYNA0001:00
+",
+            )
+            .symlink(
+                "sys/bus/i2c/drivers/i2c_hid_acpi/i2c-SYNA0001:00",
+                "../../../../devices/pci0000:00/0000:00:1f.4/i2c-0/i2c-SYNA0001:00",
+            )
+            // AMD MP2 adapters sit below a platform device named after their ACPI ID
+            .file(
+                "sys/kernel/irq/61/actions",
+                "AMDI0011:00
+",
+            )
+            .file(
+                "sys/devices/pci0000:00/0000:04:00.7/AMDI0011:00/i2c-3/i2c-ELAN0001:00/name",
+                "ELAN0001:00
+",
+            )
+            .symlink(
+                "sys/bus/i2c/drivers/i2c_hid_acpi/i2c-ELAN0001:00",
+                "../../../../devices/pci0000:00/0000:04:00.7/AMDI0011:00/i2c-3/i2c-ELAN0001:00",
+            );
+
+        let topology = discover(&fixture.roots()).unwrap();
+        assert_eq!(topology.controllers.len(), 2);
+
+        let i801 = &topology.controllers[0];
+        assert_eq!(i801.name, "0000:00:1f.4");
+        assert_eq!(i801.bus_num, 0);
+        assert_eq!(i801.irq.as_deref(), Some("16"));
 
-        let line = " 200:          0      1306 intel-gpio   33  FRMW0005:00";
-        assert_eq!(extract_acpi_name(line), Some("FRMW0005:00".to_string()));
+        let mp2 = &topology.controllers[1];
+        assert_eq!(mp2.name, "AMDI0011:00");
+        assert_eq!(mp2.irq.as_deref(), Some("61"));
+        assert_eq!(mp2.hid_devices[0].gpio_irq.as_deref(), Some("60"));
+        assert_eq!(topology.irq_chips["61"].chip, "IR-PCI-MSI");
+    }
+
+    #[test]
+    fn test_discover_resolves_hid_irq_through_sysfs() {
+        let fixture = Fixture::new();
+        fixture
+            .file(
+                "proc/interrupts",
+                "           CPU0
+ 21:       4000  IR-IO-APIC   21-fasteoi   i2c_designware.0
+ 72:        310  PDC   55 Level     SYNA0001:00
+",
+            )
+            // The wake-up interrupt controller is no GPIO chip, so only an exact match finds it
+            .file("sys/kernel/irq/21/actions", "i2c_designware.0\n")
+            .file("sys/kernel/irq/72/actions", "SYNA0001:00\n")
+            .file("sys/kernel/irq/72/chip_name", "PDC\n")
+            .file("sys/kernel/irq/72/hwirq", "55\n")
+            .file("sys/kernel/irq/72/type", "level\n")
+            .file(
+                "sys/devices/platform/i2c_designware.0/i2c-0/i2c-SYNA0001:00/name",
+                "SYNA0001:00\n",
+            )
+            .symlink(
+                "sys/bus/i2c/drivers/i2c_hid_acpi/i2c-SYNA0001:00",
+                "../../../../devices/platform/i2c_designware.0/i2c-0/i2c-SYNA0001:00",
+            );
+
+        let topology = discover(&fixture.roots()).unwrap();
+        assert!(topology.gpio_irqs.is_empty());
+
+        let device = &topology.controllers[0].hid_devices[0];
+        assert_eq!(device.gpio_irq.as_deref(), Some("72"));
+        assert_eq!(topology.irq_chips["72"].describe(), "PDC 55-level");
+    }
+
+    #[test]
+    fn test_discover_device_tree() {
+        let fixture = Fixture::new();
+        let dt = "sys/firmware/devicetree/base/soc@0";
+        let geni = "sys/devices/platform/soc@0/ac0000.geniqup/a8c000.i2c/i2c-2";
+        fixture
+            .file(
+                "proc/interrupts",
+                "           CPU0
+183:       5000     GICv3 620 Level     a8c000.i2c
+219:        150  msmgpio  81 Edge      hid-over-i2c
+220:         20  msmgpio  82 Edge      hid-over-i2c
+250:         90  mt-eint  11 Level     gt7375p
+",
+            )
+            .file(
+                &format!("{dt}/pinctrl@f100000/phandle"),
+                0x2au32.to_be_bytes(),
+            )
+            .file(&format!("{dt}/pinctrl@f100000/gpio-controller"), "")
+            .file(&format!("{dt}/eint@1000b000/phandle"), 0x3u32.to_be_bytes())
+            .file(&format!("{dt}/interrupt-parent"), 0x2au32.to_be_bytes())
+            // Two "hid-over-i2c" devices, told apart by their pin
+            .file(
+                &format!("{dt}/i2c@a8c000/touchpad@15/compatible"),
+                "hid-over-i2c\0",
+            )
+            .file(
+                &format!("{dt}/i2c@a8c000/touchpad@15/interrupts"),
+                [81u32, 2].map(u32::to_be_bytes).concat(),
+            )
+            .file(
+                &format!("{dt}/i2c@a8c000/keyboard@3a/compatible"),
+                "hid-over-i2c\0",
+            )
+            .file(
+                &format!("{dt}/i2c@a8c000/keyboard@3a/interrupts"),
+                [82u32, 2].map(u32::to_be_bytes).concat(),
+            )
+            .file(
+                &format!("{dt}/i2c@11009000/touchscreen@5d/compatible"),
+                "goodix,gt7375p\0",
+            )
+            .file(
+                &format!("{dt}/i2c@11009000/touchscreen@5d/interrupts-extended"),
+                [3u32, 11, 4].map(u32::to_be_bytes).concat(),
+            )
+            .file(&format!("{geni}/2-0015/name"), "hid-over-i2c\n")
+            .symlink(
+                &format!("{geni}/2-0015/of_node"),
+                "../../../../../../../firmware/devicetree/base/soc@0/i2c@a8c000/touchpad@15",
+            )
+            .file(&format!("{geni}/2-003a/name"), "hid-over-i2c\n")
+            .symlink(
+                &format!("{geni}/2-003a/of_node"),
+                "../../../../../../../firmware/devicetree/base/soc@0/i2c@a8c000/keyboard@3a",
+            )
+            .file(
+                "sys/devices/platform/soc/11009000.i2c/i2c-4/4-005d/name",
+                "gt7375p\n",
+            )
+            .symlink(
+                "sys/devices/platform/soc/11009000.i2c/i2c-4/4-005d/of_node",
+                "../../../../../../firmware/devicetree/base/soc@0/i2c@11009000/touchscreen@5d",
+            )
+            .symlink(
+                "sys/bus/i2c/drivers/i2c_hid_of/2-0015",
+                "../../../../devices/platform/soc@0/ac0000.geniqup/a8c000.i2c/i2c-2/2-0015",
+            )
+            .symlink(
+                "sys/bus/i2c/drivers/i2c_hid_of/2-003a",
+                "../../../../devices/platform/soc@0/ac0000.geniqup/a8c000.i2c/i2c-2/2-003a",
+            )
+            .symlink(
+                "sys/bus/i2c/drivers/i2c_hid_of_goodix/4-005d",
+                "../../../../devices/platform/soc/11009000.i2c/i2c-4/4-005d",
+            )
+            .file(
+                "sys/bus/hid/devices/0018:04F3:3195.0001/uevent",
+                "DRIVER=hid-multitouch\nHID_PHYS=2-0015\n",
+            )
+            .file(
+                "sys/bus/hid/devices/0018:04F3:3195.0001/input/input3/name",
+                "hid-over-i2c 04F3:3195\n",
+            )
+            // Touch Pad application collection with a 29-byte input report (ID 1)
+            .file(
+                "sys/bus/hid/devices/0018:04F3:3195.0001/report_descriptor",
+                [
+                    0x05, 0x0D, 0x09, 0x05, 0xA1, 0x01, 0x85, 0x01, 0x75, 0x08, 0x95, 0x1D, 0x81,
+                    0x02, 0xC0,
+                ],
+            );
+
+        let topology = discover(&fixture.roots()).unwrap();
+        assert_eq!(topology.controllers.len(), 2);
+
+        let geni = &topology.controllers[0];
+        assert_eq!(geni.name, "a8c000.i2c");
+        assert_eq!(geni.irq.as_deref(), Some("183"));
+        let mut devices: Vec<_> = geni.hid_devices.iter().collect();
+        devices.sort_by_key(|d| d.client.clone());
+
+        assert_eq!(devices[0].client, "2-0015");
+        assert_eq!(devices[0].acpi_name, "hid-over-i2c");
+        assert_eq!(devices[0].gpio_irq.as_deref(), Some("219"));
+        assert_eq!(devices[0].vendor_id, 0x04F3);
+        assert_eq!(devices[0].device_type, "Touchpad");
+        assert_eq!(devices[0].collections[0].describe(), "Digitizer/Touch Pad");
+        assert_eq!(devices[0].max_input_report, Some(30));
+        assert_eq!(devices[1].client, "2-003a");
+        assert_eq!(devices[1].gpio_irq.as_deref(), Some("220"));
+
+        let touchscreen = &topology.controllers[1].hid_devices[0];
+        assert_eq!(topology.controllers[1].name, "11009000.i2c");
+        assert_eq!(touchscreen.acpi_name, "goodix,gt7375p");
+        assert_eq!(touchscreen.gpio_irq.as_deref(), Some("250"));
+        assert_eq!(topology.irq_chips["250"].chip, "mt-eint");
+    }
+
+    #[test]
+    fn test_discover_device_tree_three_cell_parent() {
+        let fixture = Fixture::new();
+        let dt = "sys/firmware/devicetree/base/soc@0";
+        let geni = "sys/devices/platform/soc@0/ac0000.geniqup/a8c000.i2c/i2c-2";
+        fixture
+            .file(
+                "proc/interrupts",
+                "           CPU0
+183:       5000     GICv3 620 Level     a8c000.i2c
+205:        150     GICv3 113 Level     hid-over-i2c
+206:         20     GICv3 114 Level     hid-over-i2c
+250:         90  mt-sysirq  11 Level     gt7375p
+",
+            )
+            .file(
+                &format!("{dt}/interrupt-controller@17a00000/phandle"),
+                0x1u32.to_be_bytes(),
+            )
+            .file(
+                &format!("{dt}/interrupt-controller@17a00000/#interrupt-cells"),
+                3u32.to_be_bytes(),
+            )
+            .file(
+                &format!("{dt}/interrupt-controller@17a00000/compatible"),
+                "arm,gic-v3\0",
+            )
+            .file(&format!("{dt}/intc@10200620/phandle"), 0x2u32.to_be_bytes())
+            .file(
+                &format!("{dt}/intc@10200620/#interrupt-cells"),
+                3u32.to_be_bytes(),
+            )
+            .file(
+                &format!("{dt}/intc@10200620/compatible"),
+                "mediatek,mt8183-sysirq\0",
+            )
+            .file(&format!("{dt}/interrupt-parent"), 0x1u32.to_be_bytes())
+            // Two "hid-over-i2c" devices on GIC SPIs 81 and 82 (hwirqs 113 and 114)
+            .file(
+                &format!("{dt}/i2c@a8c000/touchpad@15/interrupts"),
+                [0u32, 81, 4].map(u32::to_be_bytes).concat(),
+            )
+            .file(
+                &format!("{dt}/i2c@a8c000/keyboard@3a/interrupts"),
+                [0u32, 82, 4].map(u32::to_be_bytes).concat(),
+            )
+            // A parent whose three-cell format is not decoded
+            .file(
+                &format!("{dt}/i2c@11009000/touchscreen@5d/interrupts-extended"),
+                [2u32, 0, 115, 4].map(u32::to_be_bytes).concat(),
+            )
+            .file(&format!("{geni}/2-0015/name"), "hid-over-i2c\n")
+            .symlink(
+                &format!("{geni}/2-0015/of_node"),
+                "../../../../../../../firmware/devicetree/base/soc@0/i2c@a8c000/touchpad@15",
+            )
+            .file(&format!("{geni}/2-003a/name"), "hid-over-i2c\n")
+            .symlink(
+                &format!("{geni}/2-003a/of_node"),
+                "../../../../../../../firmware/devicetree/base/soc@0/i2c@a8c000/keyboard@3a",
+            )
+            .file(
+                "sys/devices/platform/soc/11009000.i2c/i2c-4/4-005d/name",
+                "gt7375p\n",
+            )
+            .symlink(
+                "sys/devices/platform/soc/11009000.i2c/i2c-4/4-005d/of_node",
+                "../../../../../../firmware/devicetree/base/soc@0/i2c@11009000/touchscreen@5d",
+            )
+            .symlink(
+                "sys/bus/i2c/drivers/i2c_hid_of/2-0015",
+                "../../../../devices/platform/soc@0/ac0000.geniqup/a8c000.i2c/i2c-2/2-0015",
+            )
+            .symlink(
+                "sys/bus/i2c/drivers/i2c_hid_of/2-003a",
+                "../../../../devices/platform/soc@0/ac0000.geniqup/a8c000.i2c/i2c-2/2-003a",
+            )
+            .symlink(
+                "sys/bus/i2c/drivers/i2c_hid_of_goodix/4-005d",
+                "../../../../devices/platform/soc/11009000.i2c/i2c-4/4-005d",
+            );
+
+        let topology = discover(&fixture.roots()).unwrap();
+        assert_eq!(topology.controllers.len(), 2);
+
+        let mut devices: Vec<_> = topology.controllers[0].hid_devices.iter().collect();
+        devices.sort_by_key(|d| d.client.clone());
+        assert_eq!(devices[0].client, "2-0015");
+        assert_eq!(devices[0].gpio_irq.as_deref(), Some("205"));
+        assert_eq!(devices[1].client, "2-003a");
+        assert_eq!(devices[1].gpio_irq.as_deref(), Some("206"));
+
+        // Resolved by its unique action name
+        let touchscreen = &topology.controllers[1].hid_devices[0];
+        assert_eq!(touchscreen.gpio_irq.as_deref(), Some("250"));
     }
 
     #[test]
@@ -399,5 +1122,9 @@ mod tests {
         let path =
             "../../../../devices/pci0000:00/0000:00:19.1/i2c_designware.5/i2c-5/i2c-PIXA3854:00";
         assert_eq!(extract_controller_name(path), "i2c_designware.5");
+
+        let path = "../../../../devices/pci0000:00/0000:00:1f.4/i2c-0/i2c-SYNA0001:00";
+        assert_eq!(extract_controller_name(path), "0000:00:1f.4");
+        assert_eq!(extract_controller_name("i2c-PIXA3854:00"), "unknown");
     }
 }
@@ -0,0 +1,497 @@
+//! Prometheus exporter for I2C interrupt counters.
+//!
+//! Serves `/metrics` over HTTP, or writes a file for the node_exporter
+//! textfile collector.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+
+use crate::detector::{Detector, EventKind, Phase};
+use crate::discovery::{self, I2cTopology, InterruptSourceInfo};
+use crate::rates::{self, Interval, RateTracker};
+use crate::roots::FsRoots;
+use crate::sampler::{Backend, Sampler, Snapshot};
+use crate::schedule::Schedule;
+use crate::thresholds::Thresholds;
+
+/// Content type of the Prometheus text exposition format.
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Where the metrics go.
+pub enum Output<'a> {
+    /// Serve /metrics over HTTP, running detection every interval
+    Listen {
+        addr: SocketAddr,
+        interval: Duration,
+    },
+    /// Rewrite a node_exporter textfile every interval (or once)
+    Textfile {
+        path: &'a Path,
+        interval: Duration,
+        once: bool,
+    },
+}
+
+/// Escape a label value for the text exposition format.
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Labels identifying a source, built from the topology.
+fn source_labels(source: &InterruptSourceInfo) -> String {
+    let hex = |id: Option<u16>| id.map(|id| format!("{:04x}", id)).unwrap_or_default();
+    format!(
+        "irq=\"{}\",controller=\"{}\",acpi_name=\"{}\",device_type=\"{}\",vendor_id=\"{}\",product_id=\"{}\"",
+        escape_label(&source.irq),
+        escape_label(&source.controller),
+        escape_label(source.acpi_name.as_deref().unwrap_or_default()),
+        escape_label(&source.device_type),
+        hex(source.vendor_id),
+        hex(source.product_id),
+    )
+}
+
+/// Storm and stuck-source detection on the rates between detection samples.
+pub struct Detection {
+    detector: Detector,
+    tracker: Option<RateTracker>,
+    start_timestamp: f64,
+    interval_ms: u64,
+    /// Conditions started so far, per (kind, IRQ)
+    started: HashMap<(EventKind, String), u64>,
+}
+
+impl Detection {
+    pub fn new(
+        sources: &[InterruptSourceInfo],
+        thresholds: &Thresholds,
+        min_duration_s: f64,
+        interval_ms: u64,
+    ) -> Self {
+        Self {
+            detector: Detector::new(sources, thresholds, min_duration_s),
+            tracker: None,
+            start_timestamp: 0.0,
+            interval_ms,
+            started: HashMap::new(),
+        }
+    }
+
+    /// Feed the next detection sample, logging the events it starts or ends.
+    ///
+    /// The first snapshot only sets the baseline, and intervals spanning a
+    /// suspend are skipped.
+    pub fn update(&mut self, sources: &[InterruptSourceInfo], snapshot: &Snapshot) {
+        let Some(tracker) = &mut self.tracker else {
+            self.tracker = Some(RateTracker::new(sources, snapshot, self.interval_ms));
+            self.start_timestamp = snapshot.timestamp;
+            return;
+        };
+        let Interval::Rates {
+            dt_s,
+            sources: rates,
+        } = tracker.next(snapshot)
+        else {
+            return;
+        };
+        let events = self.detector.update(
+            snapshot.timestamp - self.start_timestamp,
+            dt_s,
+            &rates::by_irq(sources, &rates),
+        );
+        for event in events {
+            eprintln!("{:>8.1}  ! {}", event.timestamp, event.describe());
+            if event.phase == Phase::Start {
+                *self.started.entry((event.kind, event.irq)).or_default() += 1;
+            }
+        }
+    }
+}
+
+/// Detection kinds that apply to a source.
+fn condition_kinds(source: &InterruptSourceInfo) -> [EventKind; 2] {
+    if source.is_controller {
+        [EventKind::Storm, EventKind::StuckBus]
+    } else {
+        [EventKind::Storm, EventKind::SpuriousGpio]
+    }
+}
+
+/// Render the metrics of all sources for one snapshot.
+///
+/// The condition metrics are left out without detection (`--once`), where
+/// there are no rates to detect anything on.
+pub fn render_metrics(
+    sources: &[InterruptSourceInfo],
+    snapshot: &Snapshot,
+    detection: Option<&Detection>,
+) -> String {
+    let mut out = String::new();
+
+    out.push_str(
+        "# HELP i2c_int_monitor_interrupts_total Interrupts handled on an I2C controller or HID device IRQ.\n",
+    );
+    out.push_str("# TYPE i2c_int_monitor_interrupts_total counter\n");
+    for source in sources {
+        if let Some(total) = snapshot.total(&source.irq) {
+            let _ = writeln!(
+                out,
+                "i2c_int_monitor_interrupts_total{{{}}} {}",
+                source_labels(source),
+                total
+            );
+        }
+    }
+
+    out.push_str(
+        "# HELP i2c_int_monitor_cpu_interrupts_total Interrupts handled on an I2C controller or HID device IRQ, per CPU.\n",
+    );
+    out.push_str("# TYPE i2c_int_monitor_cpu_interrupts_total counter\n");
+    for source in sources {
+        let Some(per_cpu) = snapshot.counts.get(&source.irq) else {
+            continue;
+        };
+        let labels = source_labels(source);
+        for (cpu, count) in per_cpu {
+            let _ = writeln!(
+                out,
+                "i2c_int_monitor_cpu_interrupts_total{{{},cpu=\"{}\"}} {}",
+                labels, cpu, count
+            );
+        }
+    }
+
+    let Some(detection) = detection else {
+        return out;
+    };
+    let active: HashSet<(EventKind, &str)> = detection.detector.active().collect();
+    out.push_str(
+        "# HELP i2c_int_monitor_condition_active Whether a storm, stuck-bus or spurious-GPIO condition is active on a source.\n",
+    );
+    out.push_str("# TYPE i2c_int_monitor_condition_active gauge\n");
+    for source in sources {
+        for kind in condition_kinds(source) {
+            let _ = writeln!(
+                out,
+                "i2c_int_monitor_condition_active{{kind=\"{}\",{}}} {}",
+                kind.as_str(),
+                source_labels(source),
+                u8::from(active.contains(&(kind, source.irq.as_str())))
+            );
+        }
+    }
+
+    out.push_str(
+        "# HELP i2c_int_monitor_condition_events_total Storm, stuck-bus and spurious-GPIO conditions started on a source.\n",
+    );
+    out.push_str("# TYPE i2c_int_monitor_condition_events_total counter\n");
+    for source in sources {
+        for kind in condition_kinds(source) {
+            let _ = writeln!(
+                out,
+                "i2c_int_monitor_condition_events_total{{kind=\"{}\",{}}} {}",
+                kind.as_str(),
+                source_labels(source),
+                detection
+                    .started
+                    .get(&(kind, source.irq.clone()))
+                    .copied()
+                    .unwrap_or(0)
+            );
+        }
+    }
+
+    out
+}
+
+/// Run the exporter until interrupted (or once, for a one-shot textfile).
+pub fn run(
+    output: Output,
+    backend: Backend,
+    thresholds: &Thresholds,
+    min_duration_s: f64,
+    roots: &FsRoots,
+) -> Result<()> {
+    let topology = discovery::discover(roots)?;
+    let sources = topology.all_sources();
+    if sources.is_empty() {
+        anyhow::bail!("No I2C-related interrupt sources found.");
+    }
+
+    let mut sampler = backend.open(&topology, roots);
+    match output {
+        Output::Listen { addr, interval } => {
+            let listener =
+                TcpListener::bind(addr).with_context(|| format!("failed to bind {}", addr))?;
+            println!(
+                "Serving {} sources on http://{}/metrics",
+                sources.len(),
+                addr
+            );
+            let detection = Mutex::new(Detection::new(
+                &sources,
+                thresholds,
+                min_duration_s,
+                interval.as_millis() as u64,
+            ));
+            thread::scope(|scope| {
+                scope.spawn(|| detect(&topology, &sources, backend, roots, interval, &detection));
+                serve(listener, &sources, sampler.as_mut(), &detection)
+            })
+        }
+        Output::Textfile {
+            path, once: true, ..
+        } => write_textfile(path, &sources, sampler.as_mut(), None),
+        Output::Textfile { path, interval, .. } => {
+            let mut detection = Detection::new(
+                &sources,
+                thresholds,
+                min_duration_s,
+                interval.as_millis() as u64,
+            );
+            loop {
+                write_textfile(path, &sources, sampler.as_mut(), Some(&mut detection))?;
+                thread::sleep(interval);
+            }
+        }
+    }
+}
+
+/// Write the metrics atomically, so the collector never reads a partial file.
+///
+/// Each write also feeds detection, if any, so it runs on the rewrite interval.
+fn write_textfile(
+    path: &Path,
+    sources: &[InterruptSourceInfo],
+    sampler: &mut dyn Sampler,
+    mut detection: Option<&mut Detection>,
+) -> Result<()> {
+    let snapshot = sampler
+        .sample()?
+        .context("no interrupt snapshots available")?;
+    if let Some(detection) = detection.as_mut() {
+        detection.update(sources, &snapshot);
+    }
+    let tmp_path = path.with_extension("prom.tmp");
+    fs::write(
+        &tmp_path,
+        render_metrics(sources, &snapshot, detection.as_deref()),
+    )
+    .with_context(|| format!("failed to write {}", tmp_path.display()))?;
+    fs::rename(&tmp_path, path)
+        .with_context(|| format!("failed to rename to {}", path.display()))?;
+    Ok(())
+}
+
+/// Feed detection from its own sampler on a fixed schedule.
+///
+/// Scrapes only read the detection state, so neither the number of scrapers
+/// nor their intervals change the intervals `--min-duration` is measured over.
+fn detect(
+    topology: &I2cTopology,
+    sources: &[InterruptSourceInfo],
+    backend: Backend,
+    roots: &FsRoots,
+    interval: Duration,
+    detection: &Mutex<Detection>,
+) {
+    let mut sampler = backend.open(topology, roots);
+    let mut schedule = Schedule::new(interval);
+    loop {
+        match sampler.sample() {
+            Ok(Some(snapshot)) => lock(detection).update(sources, &snapshot),
+            Ok(None) => return,
+            Err(e) => eprintln!("detection sample failed: {:#}", e),
+        }
+        schedule.wait();
+        schedule.advance(Instant::now());
+    }
+}
+
+/// Lock the detection state, even if a panic poisoned it.
+fn lock(detection: &Mutex<Detection>) -> MutexGuard<'_, Detection> {
+    detection.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn serve(
+    listener: TcpListener,
+    sources: &[InterruptSourceInfo],
+    sampler: &mut dyn Sampler,
+    detection: &Mutex<Detection>,
+) -> Result<()> {
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("accept failed: {}", e);
+                continue;
+            }
+        };
+        // A misbehaving client must not stop the exporter
+        if let Err(e) = handle_connection(stream, sources, sampler, detection) {
+            eprintln!("request failed: {:#}", e);
+        }
+    }
+
+    Ok(())
+}
+
+fn handle_connection(
+    stream: TcpStream,
+    sources: &[InterruptSourceInfo],
+    sampler: &mut dyn Sampler,
+    detection: &Mutex<Detection>,
+) -> Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut reader = BufReader::new(&stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    // Drain the headers; the exporter does not need any of them
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 0 && header.trim() != "" {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let path = parts.next().unwrap_or_default();
+
+    let (status, content_type, body) = match (method, path) {
+        ("GET", "/metrics") => match sampler.sample()? {
+            Some(snapshot) => (
+                "200 OK",
+                CONTENT_TYPE,
+                render_metrics(sources, &snapshot, Some(&lock(detection))),
+            ),
+            None => (
+                "503 Service Unavailable",
+                "text/plain",
+                "no snapshot\n".to_string(),
+            ),
+        },
+        ("GET", "/") => (
+            "200 OK",
+            "text/html",
+            "<html><body><a href=\"/metrics\">Metrics</a></body></html>\n".to_string(),
+        ),
+        _ => ("404 Not Found", "text/plain", "not found\n".to_string()),
+    };
+
+    let mut stream = &stream;
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture;
+
+    fn snapshot(timestamp: f64, per_cpu: &[(u32, u64)]) -> Snapshot {
+        fixture::snapshot(timestamp, &[("203", per_cpu)])
+    }
+
+    #[test]
+    fn test_render_metrics() {
+        let sources = fixture::touchpad_sources();
+        let snapshot = snapshot(0.0, &[(0, 10), (2, 127)]);
+        let mut detection = Detection::new(&sources, &Thresholds::new(100.0), 5.0, 1000);
+        detection.update(&sources, &snapshot);
+
+        let metrics = render_metrics(&sources, &snapshot, Some(&detection));
+        let labels = "irq=\"203\",controller=\"i2c_designware.5\",acpi_name=\"PIXA3854:00\",device_type=\"Touchpad\",vendor_id=\"093a\",product_id=\"0274\"";
+        assert!(metrics.contains(&format!(
+            "i2c_int_monitor_interrupts_total{{{}}} 137\n",
+            labels
+        )));
+        assert!(metrics.contains(&format!(
+            "i2c_int_monitor_cpu_interrupts_total{{{},cpu=\"2\"}} 127\n",
+            labels
+        )));
+    }
+
+    #[test]
+    fn test_render_metrics_without_detection() {
+        let sources = fixture::touchpad_sources();
+        let metrics = render_metrics(&sources, &snapshot(0.0, &[(0, 137)]), None);
+        assert!(metrics.contains("i2c_int_monitor_interrupts_total{"));
+        assert!(!metrics.contains("i2c_int_monitor_condition"));
+    }
+
+    #[test]
+    fn test_condition_metrics() {
+        let sources = fixture::touchpad_sources();
+        let mut detection = Detection::new(&sources, &Thresholds::new(100.0), 5.0, 1000);
+        let labels = "irq=\"203\",controller=\"i2c_designware.5\",acpi_name=\"PIXA3854:00\",device_type=\"Touchpad\",vendor_id=\"093a\",product_id=\"0274\"";
+        let metric = |detection: &Detection, snapshot: &Snapshot, name: &str| {
+            let prefix = format!("{}{{kind=\"storm\",{}}} ", name, labels);
+            render_metrics(&sources, snapshot, Some(detection))
+                .lines()
+                .find_map(|l| l.strip_prefix(&prefix).map(str::to_string))
+                .unwrap()
+        };
+
+        // 500 irqs/s for 10s between two textfile writes
+        for (timestamp, count) in [(0.0, 0), (10.0, 5000)] {
+            detection.update(&sources, &snapshot(timestamp, &[(0, count)]));
+        }
+        let current = snapshot(10.0, &[(0, 5000)]);
+        assert_eq!(
+            metric(&detection, &current, "i2c_int_monitor_condition_active"),
+            "1"
+        );
+        assert_eq!(
+            metric(
+                &detection,
+                &current,
+                "i2c_int_monitor_condition_events_total"
+            ),
+            "1"
+        );
+
+        let current = snapshot(20.0, &[(0, 5000)]);
+        detection.update(&sources, &current);
+        assert_eq!(
+            metric(&detection, &current, "i2c_int_monitor_condition_active"),
+            "0"
+        );
+        assert_eq!(
+            metric(
+                &detection,
+                &current,
+                "i2c_int_monitor_condition_events_total"
+            ),
+            "1"
+        );
+        assert!(
+            render_metrics(&sources, &current, Some(&detection))
+                .contains("i2c_int_monitor_condition_active{kind=\"spurious_gpio\",")
+        );
+    }
+
+    #[test]
+    fn test_escape_label() {
+        assert_eq!(escape_label("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+}
@@ -0,0 +1,113 @@
+//! Temporary procfs/sysfs trees for tests.
+
+use std::collections::HashMap;
+use std::fs;
+use std::os::unix::fs::symlink;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::discovery::{HidDevice, I2cController, I2cTopology, InterruptSourceInfo};
+use crate::roots::FsRoots;
+use crate::sampler::Snapshot;
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// A fixture directory with `proc/` and `sys/` subtrees, removed on drop.
+pub struct Fixture {
+    root: PathBuf,
+}
+
+impl Fixture {
+    pub fn new() -> Self {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let root = std::env::temp_dir().join(format!("i2c-int-monitor-{}-{}", process::id(), id));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("proc")).unwrap();
+        fs::create_dir_all(root.join("sys")).unwrap();
+        Self { root }
+    }
+
+    /// Roots pointing into this fixture.
+    pub fn roots(&self) -> FsRoots {
+        FsRoots {
+            proc: self.root.join("proc"),
+            sys: self.root.join("sys"),
+        }
+    }
+
+    /// Write a file relative to the fixture root, creating parent directories.
+    pub fn file(&self, relative: &str, content: impl AsRef<[u8]>) -> &Self {
+        let path = self.root.join(relative);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+        self
+    }
+
+    /// Create a symlink relative to the fixture root pointing at `target`.
+    pub fn symlink(&self, relative: &str, target: impl AsRef<Path>) -> &Self {
+        let path = self.root.join(relative);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        symlink(target, path).unwrap();
+        self
+    }
+}
+
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+/// One PIXA3854:00 touchpad (IRQ 203) on i2c_designware.5 (IRQ 21).
+pub fn touchpad_topology() -> I2cTopology {
+    let device = HidDevice {
+        acpi_name: "PIXA3854:00".to_string(),
+        client: "i2c-PIXA3854:00".to_string(),
+        vendor_id: 0x093A,
+        product_id: 0x0274,
+        device_type: "Touchpad".to_string(),
+        driver: "hid-multitouch".to_string(),
+        bus_num: 5,
+        controller: "i2c_designware.5".to_string(),
+        gpio_irq: Some("203".to_string()),
+        input_names: Vec::new(),
+        collections: Vec::new(),
+        max_input_report: None,
+        power: None,
+        sysfs_dir: None,
+    };
+    I2cTopology {
+        controllers: vec![I2cController {
+            name: "i2c_designware.5".to_string(),
+            bus_num: 5,
+            irq: Some("21".to_string()),
+            power: None,
+            sysfs_dir: None,
+            hid_devices: vec![device],
+        }],
+        gpio_irqs: HashMap::new(),
+        controller_irqs: HashMap::new(),
+        irq_chips: HashMap::new(),
+    }
+}
+
+/// The sources of [`touchpad_topology`]: the controller, then the touchpad.
+pub fn touchpad_sources() -> Vec<InterruptSourceInfo> {
+    touchpad_topology().all_sources()
+}
+
+/// A snapshot holding only the given (CPU id, count) pairs per IRQ.
+pub fn snapshot(timestamp: f64, counts: &[(&str, &[(u32, u64)])]) -> Snapshot {
+    Snapshot {
+        timestamp,
+        counts: counts
+            .iter()
+            .map(|(irq, per_cpu)| (irq.to_string(), per_cpu.to_vec()))
+            .collect(),
+        power: HashMap::new(),
+        idle: Vec::new(),
+        energy: Vec::new(),
+        suspend: None,
+    }
+}
@@ -0,0 +1,291 @@
+//! Minimal HID report descriptor parser.
+//!
+//! Extracts the top-level application collections and the input report sizes,
+//! which is enough to classify a device and to estimate its I2C transfer size.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+const PAGE_GENERIC_DESKTOP: u16 = 0x01;
+const PAGE_DIGITIZER: u16 = 0x0D;
+const PAGE_CONSUMER: u16 = 0x0C;
+const PAGE_SENSOR: u16 = 0x20;
+const PAGE_VENDOR_MIN: u16 = 0xFF00;
+
+/// A top-level application collection, identified by its usage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HidCollection {
+    pub usage_page: u16,
+    pub usage: u16,
+}
+
+impl HidCollection {
+    /// Human-readable usage (e.g., "Digitizer/Touch Pad").
+    pub fn describe(&self) -> String {
+        let usage = match (self.usage_page, self.usage) {
+            (PAGE_GENERIC_DESKTOP, 0x01) => Some("Pointer"),
+            (PAGE_GENERIC_DESKTOP, 0x02) => Some("Mouse"),
+            (PAGE_GENERIC_DESKTOP, 0x04) => Some("Joystick"),
+            (PAGE_GENERIC_DESKTOP, 0x05) => Some("Gamepad"),
+            (PAGE_GENERIC_DESKTOP, 0x06) => Some("Keyboard"),
+            (PAGE_GENERIC_DESKTOP, 0x0C) => Some("Wireless Radio Controls"),
+            (PAGE_GENERIC_DESKTOP, 0x0E) => Some("System Multi-Axis Controller"),
+            (PAGE_GENERIC_DESKTOP, 0x80) => Some("System Control"),
+            (PAGE_DIGITIZER, 0x01) => Some("Digitizer"),
+            (PAGE_DIGITIZER, 0x02) => Some("Pen"),
+            (PAGE_DIGITIZER, 0x04) => Some("Touch Screen"),
+            (PAGE_DIGITIZER, 0x05) => Some("Touch Pad"),
+            (PAGE_DIGITIZER, 0x0E) => Some("Device Configuration"),
+            (PAGE_CONSUMER, 0x01) => Some("Consumer Control"),
+            _ => None,
+        };
+        let page = match self.usage_page {
+            PAGE_GENERIC_DESKTOP => "Generic Desktop".to_string(),
+            PAGE_DIGITIZER => "Digitizer".to_string(),
+            PAGE_CONSUMER => "Consumer".to_string(),
+            PAGE_SENSOR => "Sensor".to_string(),
+            page if page >= PAGE_VENDOR_MIN => format!("Vendor 0x{:04X}", page),
+            page => format!("Page 0x{:02X}", page),
+        };
+        match usage {
+            Some(usage) => format!("{}/{}", page, usage),
+            None => format!("{}/0x{:02X}", page, self.usage),
+        }
+    }
+}
+
+/// What a report descriptor declares.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ReportDescriptor {
+    /// Top-level application collections, in descriptor order
+    pub collections: Vec<HidCollection>,
+    /// Size of the largest input report in bytes, including the report ID
+    pub max_input_report: Option<usize>,
+}
+
+impl ReportDescriptor {
+    /// Device type implied by the application collections, most specific first.
+    pub fn device_type(&self) -> Option<&'static str> {
+        let has = |page: u16, usage: u16| {
+            self.collections
+                .iter()
+                .any(|c| c.usage_page == page && c.usage == usage)
+        };
+        let has_page = |page: u16| self.collections.iter().any(|c| c.usage_page == page);
+
+        if has(PAGE_DIGITIZER, 0x05) {
+            Some("Touchpad")
+        } else if has(PAGE_DIGITIZER, 0x04) {
+            Some("Touchscreen")
+        } else if has(PAGE_DIGITIZER, 0x02) {
+            Some("Stylus")
+        } else if has_page(PAGE_SENSOR) {
+            Some("Sensor Hub")
+        } else if has(PAGE_GENERIC_DESKTOP, 0x06) {
+            Some("Keyboard")
+        } else if has(PAGE_CONSUMER, 0x01) {
+            Some("Consumer Control")
+        } else if has(PAGE_GENERIC_DESKTOP, 0x02) {
+            Some("Mouse")
+        } else if self
+            .collections
+            .iter()
+            .any(|c| c.usage_page >= PAGE_VENDOR_MIN)
+        {
+            Some("Vendor")
+        } else {
+            None
+        }
+    }
+}
+
+/// Global item state that Push/Pop save and restore.
+#[derive(Debug, Default, Clone, Copy)]
+struct Globals {
+    usage_page: u16,
+    report_size: u32,
+    report_count: u32,
+    report_id: u8,
+}
+
+/// Parse a binary report descriptor.
+///
+/// Parsing stops at the first truncated item; whatever was parsed up to
+/// there is returned.
+pub fn parse(descriptor: &[u8]) -> ReportDescriptor {
+    let mut globals = Globals::default();
+    let mut stack = Vec::new();
+    let mut usages: Vec<(Option<u16>, u16)> = Vec::new();
+    let mut depth = 0usize;
+    let mut collections = Vec::new();
+    let mut input_bits: HashMap<u8, u32> = HashMap::new();
+
+    let mut pos = 0;
+    while pos < descriptor.len() {
+        let prefix = descriptor[pos];
+
+        // Long items carry their size in the next byte and are never needed here
+        if prefix == 0xFE {
+            let Some(&size) = descriptor.get(pos + 1) else {
+                break;
+            };
+            pos += 3 + size as usize;
+            continue;
+        }
+
+        let size = match prefix & 0x03 {
+            3 => 4,
+            n => n as usize,
+        };
+        let Some(data) = descriptor.get(pos + 1..pos + 1 + size) else {
+            break;
+        };
+        pos += 1 + size;
+
+        let value = data
+            .iter()
+            .rev()
+            .fold(0u32, |acc, &b| (acc << 8) | b as u32);
+        let item_type = (prefix >> 2) & 0x03;
+        let tag = prefix >> 4;
+
+        match (item_type, tag) {
+            // Main items
+            (0, 0x8) => {
+                let bits = globals.report_size * globals.report_count;
+                *input_bits.entry(globals.report_id).or_default() += bits;
+            }
+            (0, 0xA) => {
+                // Application collection at the top level
+                if depth == 0 && value == 0x01 {
+                    let (page, usage) = usages.first().copied().unwrap_or((None, 0));
+                    collections.push(HidCollection {
+                        usage_page: page.unwrap_or(globals.usage_page),
+                        usage,
+                    });
+                }
+                depth += 1;
+            }
+            (0, 0xC) => depth = depth.saturating_sub(1),
+            // Global items
+            (1, 0x0) => globals.usage_page = value as u16,
+            (1, 0x7) => globals.report_size = value,
+            (1, 0x8) => globals.report_id = value as u8,
+            (1, 0x9) => globals.report_count = value,
+            (1, 0xA) => stack.push(globals),
+            (1, 0xB) => globals = stack.pop().unwrap_or(globals),
+            // Local usage; a 4-byte usage carries its own usage page
+            (2, 0x0) => {
+                let page = (size == 4).then_some((value >> 16) as u16);
+                usages.push((page, value as u16));
+            }
+            _ => {}
+        }
+
+        // Local items only apply up to the next main item
+        if item_type == 0 {
+            usages.clear();
+        }
+    }
+
+    let max_input_report = input_bits
+        .iter()
+        .filter(|(_, bits)| **bits > 0)
+        .map(|(&id, &bits)| bits.div_ceil(8) as usize + usize::from(id != 0))
+        .max();
+
+    ReportDescriptor {
+        collections,
+        max_input_report,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A touchpad with a finger report, a mouse report and a vendor report.
+    const TOUCHPAD: &[u8] = &[
+        0x05, 0x0D, // Usage Page (Digitizer)
+        0x09, 0x05, // Usage (Touch Pad)
+        0xA1, 0x01, // Collection (Application)
+        0x85, 0x01, //   Report ID (1)
+        0x09, 0x22, //   Usage (Finger)
+        0xA1, 0x02, //   Collection (Logical)
+        0x75, 0x01, 0x95, 0x02, // Report Size (1), Report Count (2)
+        0x81, 0x02, //     Input
+        0x75, 0x06, 0x95, 0x01, // Report Size (6), Report Count (1)
+        0x81, 0x03, //     Input (Constant)
+        0x05, 0x01, //     Usage Page (Generic Desktop)
+        0x09, 0x30, 0x09, 0x31, // Usage (X), Usage (Y)
+        0x75, 0x10, 0x95, 0x02, // Report Size (16), Report Count (2)
+        0x81, 0x02, //     Input
+        0xC0, //   End Collection
+        0xC0, // End Collection
+        0x05, 0x01, // Usage Page (Generic Desktop)
+        0x09, 0x02, // Usage (Mouse)
+        0xA1, 0x01, // Collection (Application)
+        0x85, 0x02, //   Report ID (2)
+        0x75, 0x08, 0x95, 0x03, // Report Size (8), Report Count (3)
+        0x81, 0x06, //   Input
+        0xC0, // End Collection
+        0x06, 0x00, 0xFF, // Usage Page (Vendor 0xFF00)
+        0x09, 0x01, // Usage (1)
+        0xA1, 0x01, // Collection (Application)
+        0x85, 0x03, //   Report ID (3)
+        0x75, 0x08, 0x96, 0x00, 0x01, // Report Size (8), Report Count (256)
+        0xB1, 0x02, //   Feature
+        0xC0, // End Collection
+    ];
+
+    #[test]
+    fn test_parse_touchpad() {
+        let descriptor = parse(TOUCHPAD);
+        let described: Vec<_> = descriptor
+            .collections
+            .iter()
+            .map(|c| c.describe())
+            .collect();
+        assert_eq!(
+            described,
+            [
+                "Digitizer/Touch Pad",
+                "Generic Desktop/Mouse",
+                "Vendor 0xFF00/0x01"
+            ]
+        );
+        // Report 1: 2 + 6 + 32 bits = 5 bytes, plus the report ID; feature reports do not count
+        assert_eq!(descriptor.max_input_report, Some(6));
+        assert_eq!(descriptor.device_type(), Some("Touchpad"));
+    }
+
+    #[test]
+    fn test_device_type_precedence() {
+        let descriptor = |collections: &[(u16, u16)]| ReportDescriptor {
+            collections: collections
+                .iter()
+                .map(|&(usage_page, usage)| HidCollection { usage_page, usage })
+                .collect(),
+            max_input_report: None,
+        };
+        assert_eq!(
+            descriptor(&[(0x0C, 0x01), (0x01, 0x06)]).device_type(),
+            Some("Keyboard")
+        );
+        assert_eq!(
+            descriptor(&[(0x01, 0x0C), (0x0C, 0x01)]).device_type(),
+            Some("Consumer Control")
+        );
+        assert_eq!(descriptor(&[(0xFF0B, 0x01)]).device_type(), Some("Vendor"));
+        assert_eq!(descriptor(&[(0x01, 0x80)]).device_type(), None);
+    }
+
+    #[test]
+    fn test_parse_truncated() {
+        // A 2-byte item cut short after its prefix
+        let descriptor = parse(&[0x05, 0x0D, 0x09, 0x04, 0xA1, 0x01, 0x26]);
+        assert_eq!(descriptor.collections.len(), 1);
+        assert_eq!(descriptor.max_input_report, None);
+    }
+}
@@ -3,22 +3,32 @@ use std::path::Path;
 
 use anyhow::{Context, Result};
 
-/// Represents a single interrupt source with its current count.
+use crate::roots::FsRoots;
+
+/// Represents a single interrupt source with its per-CPU counts and chip details.
 #[derive(Debug, Clone)]
 pub struct InterruptSource {
     /// IRQ number (e.g., "42", "NMI", "LOC")
     pub irq: String,
-    /// Total count across all CPUs
-    pub count: u64,
+    /// (CPU id, count) for each online CPU, in column order
+    pub per_cpu: Vec<(u32, u64)>,
+    /// Interrupt chip name (e.g., "IR-IO-APIC", "intel-gpio")
+    pub chip: Option<String>,
+    /// Hardware IRQ number within the chip (the pin number for GPIO chips)
+    pub hwirq: Option<String>,
+    /// Trigger or flow type (e.g., "edge", "level", "fasteoi")
+    pub trigger: Option<String>,
+    /// Action names registered on this IRQ (e.g., ["idma64.0", "i2c_designware.0"])
+    pub actions: Vec<String>,
 }
 
-/// Parse /proc/interrupts and return all interrupt sources.
-pub fn read_interrupts() -> Result<Vec<InterruptSource>> {
-    read_interrupts_from_path(Path::new("/proc/interrupts"))
+/// Parse /proc/interrupts below the procfs root and return all interrupt sources.
+pub fn read_interrupts(roots: &FsRoots) -> Result<Vec<InterruptSource>> {
+    read_interrupts_from_path(&roots.proc_path("interrupts"))
 }
 
 /// Parse interrupts from a specific path (useful for testing).
-fn read_interrupts_from_path(path: &Path) -> Result<Vec<InterruptSource>> {
+pub fn read_interrupts_from_path(path: &Path) -> Result<Vec<InterruptSource>> {
     let content =
         fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
 
@@ -26,16 +36,16 @@ fn read_interrupts_from_path(path: &Path) -> Result<Vec<InterruptSource>> {
 }
 
 /// Parse the content of /proc/interrupts.
-fn parse_interrupts(content: &str) -> Result<Vec<InterruptSource>> {
+pub fn parse_interrupts(content: &str) -> Result<Vec<InterruptSource>> {
     let mut sources = Vec::new();
     let mut lines = content.lines();
 
-    // First line is the header with CPU columns
+    // First line is the header with CPU columns; offline CPUs have no column
     let header = lines.next().context("empty /proc/interrupts")?;
-    let cpu_count = header.split_whitespace().count();
+    let cpus = parse_cpu_ids(header);
 
     for line in lines {
-        if let Some(source) = parse_interrupt_line(line, cpu_count) {
+        if let Some(source) = parse_interrupt_line(line, &cpus) {
             sources.push(source);
         }
     }
@@ -43,8 +53,21 @@ fn parse_interrupts(content: &str) -> Result<Vec<InterruptSource>> {
     Ok(sources)
 }
 
+/// Parse the CPU ids from the header line ("CPU0 CPU2 CPU3").
+fn parse_cpu_ids(header: &str) -> Vec<u32> {
+    header
+        .split_whitespace()
+        .enumerate()
+        .map(|(idx, col)| {
+            col.strip_prefix("CPU")
+                .and_then(|id| id.parse().ok())
+                .unwrap_or(idx as u32)
+        })
+        .collect()
+}
+
 /// Parse a single line from /proc/interrupts.
-fn parse_interrupt_line(line: &str, cpu_count: usize) -> Option<InterruptSource> {
+fn parse_interrupt_line(line: &str, cpus: &[u32]) -> Option<InterruptSource> {
     let line = line.trim();
     if line.is_empty() {
         return None;
@@ -58,20 +81,84 @@ fn parse_interrupt_line(line: &str, cpu_count: usize) -> Option<InterruptSource>
     // First part is IRQ number with colon
     let irq = parts[0].trim_end_matches(':').to_string();
 
-    // Sum counts from all CPUs
-    let mut count: u64 = 0;
+    // Collect per-CPU counts
+    let mut per_cpu = Vec::with_capacity(cpus.len());
     let mut idx = 1;
 
-    while idx < parts.len() && idx <= cpu_count {
+    while idx < parts.len() && idx <= cpus.len() {
         if let Ok(n) = parts[idx].parse::<u64>() {
-            count += n;
+            per_cpu.push((cpus[idx - 1], n));
             idx += 1;
         } else {
             break;
         }
     }
 
-    Some(InterruptSource { irq, count })
+    // Named interrupts (NMI, LOC, ...) only carry a description after the counts
+    let (chip, hwirq, trigger, actions) = if irq.chars().all(|c| c.is_ascii_digit()) {
+        parse_irq_details(&parts[idx..])
+    } else {
+        (None, None, None, Vec::new())
+    };
+
+    Some(InterruptSource {
+        irq,
+        per_cpu,
+        chip,
+        hwirq,
+        trigger,
+        actions,
+    })
+}
+
+/// Chip, hwirq, trigger and action names of a numbered interrupt line.
+type IrqDetails = (Option<String>, Option<String>, Option<String>, Vec<String>);
+
+/// Parse the columns following the CPU counts of a numbered interrupt.
+///
+/// The kernel prints the chip name, then the hwirq (when the IRQ has a domain)
+/// and flow handler joined as "18-edge", optionally an "Edge"/"Level" column,
+/// and finally the comma-separated action names.
+fn parse_irq_details(fields: &[&str]) -> IrqDetails {
+    let Some((chip, rest)) = fields.split_first() else {
+        return (None, None, None, Vec::new());
+    };
+
+    let mut hwirq = None;
+    let mut level = None;
+    let mut flow = None;
+    let mut idx = 0;
+
+    while idx < rest.len() {
+        let field = rest[idx];
+        if field == "Edge" || field == "Level" {
+            level = Some(field.to_lowercase());
+        } else if let Some((num, name)) = field.split_once('-')
+            && num.chars().all(|c| c.is_ascii_digit())
+            && !name.is_empty()
+            && hwirq.is_none()
+            && flow.is_none()
+        {
+            if !num.is_empty() {
+                hwirq = Some(num.to_string());
+            }
+            flow = Some(name.to_string());
+        } else if field.chars().all(|c| c.is_ascii_digit()) && hwirq.is_none() && flow.is_none() {
+            hwirq = Some(field.to_string());
+        } else {
+            break;
+        }
+        idx += 1;
+    }
+
+    let actions = rest[idx..]
+        .join(" ")
+        .split(',')
+        .map(|a| a.trim().to_string())
+        .filter(|a| !a.is_empty())
+        .collect();
+
+    (Some(chip.to_string()), hwirq, flow.or(level), actions)
 }
 
 #[cfg(test)]
@@ -84,6 +171,9 @@ mod tests {
  42:      12345       6789          0          0   PCI-MSI 12345-edge   i2c_designware.0
  43:        100        200          0          0   PCI-MSI 12346-edge   i2c_designware.1
  44:       5000          0          0          0   IO-APIC  44-fasteoi  PIXA3854
+ 28:        300          0          0          0  IR-IO-APIC   28-fasteoi   idma64.1, i2c_designware.1
+203:          0          0          0      21323  intel-gpio   18  PIXA3854:00
+204:          0         12          0          0  amd_gpio   5  ELAN0001:00
 NMI:          0          0          0          0   Non-maskable interrupts
 LOC:     123456     234567     345678     456789   Local timer interrupts
 "#;
@@ -97,12 +187,61 @@ LOC:     123456     234567     345678     456789   Local timer interrupts
         let i2c0 = sources.iter().find(|s| s.irq == "42");
         assert!(i2c0.is_some());
         let i2c0 = i2c0.unwrap();
-        assert_eq!(i2c0.count, 12345 + 6789);
+        assert_eq!(
+            i2c0.per_cpu.iter().map(|&(_, n)| n).sum::<u64>(),
+            12345 + 6789
+        );
+        assert_eq!(i2c0.per_cpu, vec![(0, 12345), (1, 6789), (2, 0), (3, 0)]);
 
         // Find IRQ 44
         let pixa = sources.iter().find(|s| s.irq == "44");
         assert!(pixa.is_some());
         let pixa = pixa.unwrap();
-        assert_eq!(pixa.count, 5000);
+        assert_eq!(pixa.per_cpu.iter().map(|&(_, n)| n).sum::<u64>(), 5000);
+
+        // Named interrupts have no chip details
+        let nmi = sources.iter().find(|s| s.irq == "NMI").unwrap();
+        assert!(nmi.chip.is_none());
+        assert!(nmi.actions.is_empty());
+    }
+
+    #[test]
+    fn test_parse_interrupts_offline_cpu() {
+        // CPU1 is offline, so it has no column
+        let content = "           CPU0       CPU2       CPU3
+ 42:         10         20         30   PCI-MSI 12345-edge   i2c_designware.0
+LOC:          1          2          3   Local timer interrupts
+";
+        let sources = parse_interrupts(content).unwrap();
+
+        let i2c0 = sources.iter().find(|s| s.irq == "42").unwrap();
+        assert_eq!(i2c0.per_cpu, vec![(0, 10), (2, 20), (3, 30)]);
+        assert_eq!(i2c0.actions, vec!["i2c_designware.0"]);
+
+        let loc = sources.iter().find(|s| s.irq == "LOC").unwrap();
+        assert_eq!(loc.per_cpu, vec![(0, 1), (2, 2), (3, 3)]);
+    }
+
+    #[test]
+    fn test_parse_irq_details() {
+        let sources = parse_interrupts(SAMPLE_PROC_INTERRUPTS).unwrap();
+
+        let i2c0 = sources.iter().find(|s| s.irq == "42").unwrap();
+        assert_eq!(i2c0.chip.as_deref(), Some("PCI-MSI"));
+        assert_eq!(i2c0.hwirq.as_deref(), Some("12345"));
+        assert_eq!(i2c0.trigger.as_deref(), Some("edge"));
+        assert_eq!(i2c0.actions, vec!["i2c_designware.0"]);
+
+        let shared = sources.iter().find(|s| s.irq == "28").unwrap();
+        assert_eq!(shared.chip.as_deref(), Some("IR-IO-APIC"));
+        assert_eq!(shared.trigger.as_deref(), Some("fasteoi"));
+        assert_eq!(shared.actions, vec!["idma64.1", "i2c_designware.1"]);
+
+        // GPIO lines without a flow handler name
+        let gpio = sources.iter().find(|s| s.irq == "203").unwrap();
+        assert_eq!(gpio.chip.as_deref(), Some("intel-gpio"));
+        assert_eq!(gpio.hwirq.as_deref(), Some("18"));
+        assert_eq!(gpio.trigger, None);
+        assert_eq!(gpio.actions, vec!["PIXA3854:00"]);
     }
 }
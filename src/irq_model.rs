@@ -0,0 +1,128 @@
+//! Model of DesignWare I2C controller interrupts per i2c-hid input report.
+//!
+//! i2c-hid reads each input report in one I2C read of the report plus a
+//! 2-byte length header. The DesignWare driver raises RX_FULL whenever the RX
+//! FIFO holds more than the RX threshold, refills the TX FIFO with read
+//! commands on TX_EMPTY, and finishes the transfer on STOP_DET, draining
+//! whatever is left in the RX FIFO.
+
+/// Bytes i2c-hid reads per input report on top of the report (length header).
+const LENGTH_HEADER: usize = 2;
+
+/// Measured ratios more than this factor away from the model are flagged.
+pub const OFF_MODEL_FACTOR: f64 = 1.5;
+
+/// RX FIFO parameters of a DesignWare controller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FifoConfig {
+    /// FIFO depth in bytes (IC_RX_BUFFER_DEPTH)
+    pub depth: u32,
+    /// RX threshold (IC_RX_TL); RX_FULL fires at threshold + 1 bytes
+    pub rx_threshold: u32,
+}
+
+impl Default for FifoConfig {
+    /// The upstream driver programs IC_RX_TL to 0 on a 64-byte FIFO.
+    fn default() -> Self {
+        Self {
+            depth: 64,
+            rx_threshold: 0,
+        }
+    }
+}
+
+impl FifoConfig {
+    /// Expected controller interrupts for one input report of `report_len` bytes.
+    pub fn irqs_per_report(&self, report_len: usize) -> f64 {
+        let bytes = (report_len + LENGTH_HEADER) as u32;
+        let depth = self.depth.max(1);
+        let chunk = self.rx_threshold.min(depth - 1) + 1;
+
+        let rx_full = bytes / chunk;
+        let tx_empty = bytes.div_ceil(depth);
+        let stop_det = 1;
+        (rx_full + tx_empty + stop_det) as f64
+    }
+
+    /// Expected controller IRQs per HID IRQ on one controller.
+    ///
+    /// `devices` holds the largest input report and the interrupt count of
+    /// each HID device on the controller. Devices are weighted by their
+    /// interrupt counts, or equally while all of them are idle; devices with
+    /// an unknown report size are left out.
+    pub fn expected_ratio(&self, devices: &[(Option<usize>, u64)]) -> Option<f64> {
+        let known: Vec<(f64, u64)> = devices
+            .iter()
+            .filter_map(|&(len, count)| Some((self.irqs_per_report(len?), count)))
+            .collect();
+        if known.is_empty() {
+            return None;
+        }
+
+        let total: u64 = known.iter().map(|(_, count)| count).sum();
+        if total == 0 {
+            return Some(known.iter().map(|(irqs, _)| irqs).sum::<f64>() / known.len() as f64);
+        }
+        Some(
+            known
+                .iter()
+                .map(|(irqs, count)| irqs * *count as f64)
+                .sum::<f64>()
+                / total as f64,
+        )
+    }
+}
+
+/// Whether the model applies to a controller (DesignWare only).
+pub fn applies_to(controller: &str) -> bool {
+    controller.starts_with("i2c_designware")
+}
+
+/// Whether a measured ratio is far off the expected one.
+pub fn is_off_model(measured: f64, expected: f64) -> bool {
+    measured > expected * OFF_MODEL_FACTOR || measured < expected / OFF_MODEL_FACTOR
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_irqs_per_report() {
+        // A 30-byte report is a 32-byte read: one RX_FULL per byte at threshold 0
+        let fifo = FifoConfig::default();
+        assert_eq!(fifo.irqs_per_report(30), 34.0);
+
+        // With a threshold of 7, RX_FULL fires every 8 bytes
+        let fifo = FifoConfig {
+            depth: 64,
+            rx_threshold: 7,
+        };
+        assert_eq!(fifo.irqs_per_report(30), 6.0);
+
+        // A threshold beyond the FIFO is clamped to its depth
+        let fifo = FifoConfig {
+            depth: 16,
+            rx_threshold: 100,
+        };
+        assert_eq!(fifo.irqs_per_report(30), 2.0 + 2.0 + 1.0);
+    }
+
+    #[test]
+    fn test_expected_ratio() {
+        let fifo = FifoConfig::default();
+        // 34 IRQs per touchpad report, 10 per keyboard report; the touchpad fires 3x as often
+        let ratio = fifo.expected_ratio(&[(Some(30), 300), (Some(6), 100), (None, 1000)]);
+        assert_eq!(ratio, Some(28.0));
+        assert_eq!(
+            fifo.expected_ratio(&[(Some(30), 0), (Some(6), 0)]),
+            Some(22.0)
+        );
+        assert_eq!(fifo.expected_ratio(&[(None, 10)]), None);
+
+        assert!(!is_off_model(30.0, 34.0));
+        assert!(is_off_model(4.0, 34.0));
+        assert!(applies_to("i2c_designware.5"));
+        assert!(!applies_to("AMDI0011:00"));
+    }
+}
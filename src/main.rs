@@ -1,27 +1,155 @@
+mod benchmark;
+mod capture;
+mod check;
+mod compare;
+mod cpuidle;
+mod detector;
+mod devicetree;
 mod discovery;
+mod exporter;
+#[cfg(test)]
+mod fixture;
+mod hid_descriptor;
 mod interrupts;
+mod irq_model;
+mod output;
+mod power;
+mod rapl;
+mod rates;
+mod recording;
+mod roots;
+mod sampler;
+mod schedule;
+mod stats;
+mod suspend;
+mod sysirq;
+mod thresholds;
 mod tui;
-
-use std::collections::HashMap;
-use std::thread;
-use std::time::Duration;
-
-use anyhow::Result;
-use clap::{Parser, Subcommand};
+mod workload;
+
+use std::ffi::OsString;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use clap::{Args, Parser, Subcommand};
+
+use benchmark::Phase;
+use capture::{CaptureConfig, RatioBand, Triggers};
+use check::MaxRule;
+use irq_model::FifoConfig;
+use output::Format;
+use rates::{Interval, RateTracker};
+use recording::Header;
+use roots::FsRoots;
+use sampler::{Backend, SampleSource};
+use schedule::Schedule;
+use thresholds::{LevelTracker, Rule, Thresholds};
 
 #[derive(Parser)]
 #[command(name = "i2c-int-monitor")]
 #[command(about = "I2C and HID interrupt rate monitor")]
 #[command(version)]
 struct Cli {
+    /// Root of the procfs tree to read (e.g., a capture from another machine)
+    #[arg(long, global = true, value_name = "DIR", default_value = "/proc")]
+    proc_root: PathBuf,
+
+    /// Root of the sysfs tree to read (e.g., a capture from another machine)
+    #[arg(long, global = true, value_name = "DIR", default_value = "/sys")]
+    sys_root: PathBuf,
+
     #[command(subcommand)]
     command: Command,
 }
 
+/// Options selecting where interrupt counts are sampled from.
+#[derive(Args)]
+struct SampleArgs {
+    /// Backend for reading live interrupt counts
+    #[arg(long, value_enum, default_value_t = Backend::Procfs)]
+    backend: Backend,
+
+    /// Replay snapshots from a recording file instead of sampling live
+    #[arg(long, value_name = "FILE")]
+    replay: Option<PathBuf>,
+}
+
+impl SampleArgs {
+    fn source(self) -> SampleSource {
+        match self.replay {
+            Some(path) => SampleSource::Replay(path),
+            None => SampleSource::Live(self.backend),
+        }
+    }
+}
+
+/// DesignWare RX FIFO parameters for the expected controller/HID ratio.
+#[derive(Args)]
+struct FifoArgs {
+    /// RX FIFO depth of the DesignWare controllers, in bytes
+    #[arg(long, default_value_t = 64)]
+    rx_fifo_depth: u32,
+
+    /// RX FIFO threshold (IC_RX_TL) programmed by the driver
+    #[arg(long, default_value_t = 0)]
+    rx_threshold: u32,
+}
+
+impl FifoArgs {
+    fn config(&self) -> FifoConfig {
+        FifoConfig {
+            depth: self.rx_fifo_depth,
+            rx_threshold: self.rx_threshold,
+        }
+    }
+}
+
+/// Rate thresholds: a global default and per-source rules.
+#[derive(Args)]
+struct ThresholdArgs {
+    /// Threshold for highlighting high rates (irqs/s), for sources without a --limit
+    #[arg(long, short, default_value_t = 100.0)]
+    threshold: f64,
+
+    /// Per-source limit KEY=WARN[,CRITICAL][,for=SECS][,hysteresis=PCT]; KEY is an IRQ, ACPI name, controller or device type
+    #[arg(long = "limit", value_name = "RULE")]
+    limits: Vec<Rule>,
+
+    /// File with one --limit rule per line (applied before --limit)
+    #[arg(long, value_name = "FILE")]
+    limits_file: Option<PathBuf>,
+}
+
+impl ThresholdArgs {
+    fn thresholds(self) -> Result<Thresholds> {
+        let mut thresholds = Thresholds::new(self.threshold);
+        if let Some(path) = &self.limits_file {
+            thresholds.rules = thresholds::load_rules(path)?;
+        }
+        thresholds.rules.extend(self.limits);
+        Ok(thresholds)
+    }
+}
+
+/// Options of the storm and stuck-source detector.
+#[derive(Args)]
+struct DetectArgs {
+    /// Seconds a storm or stuck condition must hold before it is logged
+    #[arg(long, value_name = "SECS", default_value_t = detector::DEFAULT_MIN_DURATION_S)]
+    min_duration: f64,
+}
+
 #[derive(Subcommand)]
 enum Command {
     /// List detected I2C devices and their interrupt sources
-    List,
+    List {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+    },
 
     /// Monitor interrupt rates in text mode
     Monitor {
@@ -33,9 +161,18 @@ enum Command {
         #[arg(long, short = 'n', default_value_t = 0)]
         count: u32,
 
-        /// Threshold for highlighting high rates (irqs/s)
-        #[arg(long, short, default_value_t = 100.0)]
-        threshold: f64,
+        #[command(flatten)]
+        thresholds: ThresholdArgs,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+
+        #[command(flatten)]
+        sample: SampleArgs,
+
+        #[command(flatten)]
+        detect: DetectArgs,
     },
 
     /// Live TUI dashboard with charts
@@ -44,31 +181,325 @@ enum Command {
         #[arg(long, short, default_value_t = 1000)]
         interval: u64,
 
-        /// Threshold for highlighting high rates (irqs/s)
-        #[arg(long, short, default_value_t = 100.0)]
-        threshold: f64,
+        #[command(flatten)]
+        thresholds: ThresholdArgs,
+
+        /// Replay speed multiplier (with --replay)
+        #[arg(long, default_value_t = 1.0)]
+        speed: f64,
+
+        #[command(flatten)]
+        sample: SampleArgs,
+
+        #[command(flatten)]
+        fifo: FifoArgs,
+
+        #[command(flatten)]
+        detect: DetectArgs,
+    },
+
+    /// Record topology and raw interrupt counts to a file for later replay
+    Record {
+        /// Output file
+        #[arg(long, short)]
+        output: PathBuf,
+
+        /// Sampling interval in milliseconds
+        #[arg(long, short, default_value_t = 1000)]
+        interval: u64,
+
+        /// Number of samples (0 = until interrupted)
+        #[arg(long, short = 'n', default_value_t = 0)]
+        count: u32,
+
+        /// Backend for reading live interrupt counts
+        #[arg(long, value_enum, default_value_t = Backend::Procfs)]
+        backend: Backend,
+    },
+
+    /// Sample for a while and fail if any rule is exceeded (exit 0 pass, 3 fail, 4 no devices)
+    Check {
+        /// Seconds to sample for
+        #[arg(long, short, default_value_t = 10.0)]
+        duration: f64,
+
+        /// Sampling interval in milliseconds
+        #[arg(long, short, default_value_t = 1000)]
+        interval: u64,
+
+        /// Maximum per-interval rate KEY=IRQS_PER_S; KEY is an IRQ, ACPI name, controller or device type
+        #[arg(long, value_name = "KEY=MAX", required_unless_present = "max_ratio")]
+        max_rate: Vec<MaxRule>,
+
+        /// Maximum controller/HID ratio over the whole run, CONTROLLER=RATIO
+        #[arg(long, value_name = "KEY=MAX")]
+        max_ratio: Vec<MaxRule>,
+
+        #[command(flatten)]
+        sample: SampleArgs,
+    },
+
+    /// Wait for a trigger and write the snapshots around it, like an oscilloscope
+    Capture {
+        /// Sampling interval in milliseconds
+        #[arg(long, short, default_value_t = 100)]
+        interval: u64,
+
+        /// Seconds to keep before a trigger
+        #[arg(long, default_value_t = 10.0)]
+        pre: f64,
+
+        /// Seconds to keep after a trigger
+        #[arg(long, default_value_t = 10.0)]
+        post: f64,
+
+        /// Trigger when a source matching KEY exceeds MAX irqs/s in an interval
+        #[arg(long, value_name = "KEY=MAX", required_unless_present = "ratio_band")]
+        max_rate: Vec<MaxRule>,
+
+        /// Trigger when the controller/HID ratio of a matching controller leaves MIN:MAX
+        #[arg(long, value_name = "KEY=MIN:MAX")]
+        ratio_band: Vec<RatioBand>,
+
+        /// Directory to write capture-<time>-<n>.jsonl files to
+        #[arg(long, short, default_value = ".")]
+        output_dir: PathBuf,
+
+        /// Stop after this many captures (0 = until interrupted)
+        #[arg(long, short = 'n', default_value_t = 0)]
+        count: u32,
+
+        #[command(flatten)]
+        sample: SampleArgs,
+    },
+
+    /// Run a command and report the interrupts during its lifetime, like time(1)
+    Run {
+        /// Backend for reading live interrupt counts
+        #[arg(long, value_enum, default_value_t = Backend::Procfs)]
+        backend: Backend,
+
+        /// Command and arguments (after --)
+        #[arg(required = true, trailing_var_arg = true, value_name = "COMMAND")]
+        command: Vec<OsString>,
+    },
+
+    /// Guided benchmark through prompted phases (e.g., idle, touchpad swipe, typing)
+    Benchmark {
+        /// Phase NAME:SECS[:PROMPT], repeatable (default: idle, touchpad and typing, 10s each)
+        #[arg(long = "phase", value_name = "NAME:SECS[:PROMPT]")]
+        phases: Vec<Phase>,
+
+        /// Sampling interval in milliseconds
+        #[arg(long, short, default_value_t = 1000)]
+        interval: u64,
+
+        /// Countdown before each phase in seconds
+        #[arg(long, default_value_t = 3)]
+        prepare: u64,
+
+        /// File to save the results to
+        #[arg(long, short, default_value = "benchmark.json")]
+        output: PathBuf,
+
+        /// Backend for reading live interrupt counts
+        #[arg(long, value_enum, default_value_t = Backend::Procfs)]
+        backend: Backend,
+
+        /// Instead of running, show a saved result against a baseline result
+        #[arg(long, num_args = 2, value_names = ["BASELINE", "RESULT"], conflicts_with = "phases")]
+        compare: Option<Vec<PathBuf>>,
+    },
+
+    /// Compare two recordings (e.g., before and after a kernel patch)
+    Compare {
+        /// Recording taken before the change
+        before: PathBuf,
+
+        /// Recording taken after the change
+        after: PathBuf,
+    },
+
+    /// Export interrupt counters as Prometheus metrics
+    Serve {
+        /// Address to serve /metrics on
+        #[arg(long, default_value = "127.0.0.1:9847")]
+        listen: SocketAddr,
+
+        /// Write a node_exporter textfile collector file instead of serving HTTP
+        #[arg(long, value_name = "FILE")]
+        textfile: Option<PathBuf>,
+
+        /// Detection interval in milliseconds (with --textfile, the rewrite interval)
+        #[arg(long, short, default_value_t = 15000)]
+        interval: u64,
+
+        /// Write the textfile once and exit (e.g., from a cron job or timer)
+        #[arg(long, requires = "textfile")]
+        once: bool,
+
+        /// Backend for reading live interrupt counts
+        #[arg(long, value_enum, default_value_t = Backend::Procfs)]
+        backend: Backend,
+
+        #[command(flatten)]
+        thresholds: ThresholdArgs,
+
+        #[command(flatten)]
+        detect: DetectArgs,
     },
 }
 
-fn main() -> Result<()> {
+fn main() -> Result<ExitCode> {
     let cli = Cli::parse();
-
-    match cli.command {
-        Command::List => cmd_list(),
+    let roots = FsRoots {
+        proc: cli.proc_root,
+        sys: cli.sys_root,
+    };
+
+    let result = match cli.command {
+        Command::Check {
+            duration,
+            interval,
+            max_rate,
+            max_ratio,
+            sample,
+        } => {
+            return check::run(
+                duration,
+                interval,
+                &max_rate,
+                &max_ratio,
+                &sample.source(),
+                &roots,
+            )
+            .map(ExitCode::from);
+        }
+        Command::Capture {
+            interval,
+            pre,
+            post,
+            max_rate,
+            ratio_band,
+            output_dir,
+            count,
+            sample,
+        } => capture::run(
+            &CaptureConfig {
+                interval_ms: interval,
+                pre_s: pre,
+                post_s: post,
+                output_dir,
+                max_captures: count,
+            },
+            &Triggers {
+                max_rates: max_rate,
+                ratio_bands: ratio_band,
+            },
+            &sample.source(),
+            &roots,
+        ),
+        Command::Run { backend, command } => {
+            return workload::run(&command, backend, &roots).map(ExitCode::from);
+        }
+        Command::List { format } => cmd_list(format, &roots),
         Command::Monitor {
             interval,
             count,
-            threshold,
-        } => cmd_monitor(interval, count, threshold),
+            thresholds,
+            format,
+            sample,
+            detect,
+        } => cmd_monitor(
+            interval,
+            count,
+            thresholds.thresholds()?,
+            detect.min_duration,
+            format,
+            &sample.source(),
+            &roots,
+        ),
         Command::Tui {
             interval,
-            threshold,
-        } => tui::run(interval, threshold),
-    }
+            thresholds,
+            speed,
+            sample,
+            fifo,
+            detect,
+        } => tui::run(
+            interval,
+            thresholds.thresholds()?,
+            speed,
+            fifo.config(),
+            detect.min_duration,
+            &sample.source(),
+            &roots,
+        ),
+        Command::Record {
+            output,
+            interval,
+            count,
+            backend,
+        } => cmd_record(&output, interval, count, backend, &roots),
+        Command::Benchmark {
+            compare: Some(files),
+            ..
+        } => benchmark::compare(&files[0], &files[1]),
+        Command::Benchmark {
+            phases,
+            interval,
+            prepare,
+            output,
+            backend,
+            compare: None,
+        } => {
+            let phases = if phases.is_empty() {
+                Phase::defaults()
+            } else {
+                phases
+            };
+            benchmark::run(&phases, interval, prepare, &output, backend, &roots)
+        }
+        Command::Compare { before, after } => compare::run(&before, &after),
+        Command::Serve {
+            listen,
+            textfile,
+            interval,
+            once,
+            backend,
+            thresholds,
+            detect,
+        } => {
+            let output = match &textfile {
+                Some(path) => exporter::Output::Textfile {
+                    path,
+                    interval: Duration::from_millis(interval),
+                    once,
+                },
+                None => exporter::Output::Listen {
+                    addr: listen,
+                    interval: Duration::from_millis(interval),
+                },
+            };
+            exporter::run(
+                output,
+                backend,
+                &thresholds.thresholds()?,
+                detect.min_duration,
+                &roots,
+            )
+        }
+    };
+    result.map(|()| ExitCode::SUCCESS)
 }
 
-fn cmd_list() -> Result<()> {
-    let topology = discovery::discover()?;
+fn cmd_list(format: Format, roots: &FsRoots) -> Result<()> {
+    let topology = discovery::discover(roots)?;
+
+    if format != Format::Text {
+        print!("{}", output::render_topology(&topology, format)?);
+        return Ok(());
+    }
 
     if topology.controllers.is_empty() {
         println!("No I2C controllers with HID devices found.");
@@ -87,19 +518,22 @@ fn cmd_list() -> Result<()> {
         let irq_str = controller
             .irq
             .as_ref()
-            .map(|i| format!(" (IRQ {})", i))
+            .map(|i| format!(" ({})", describe_irq(&topology, i)))
             .unwrap_or_default();
         println!(
             "{} [bus {}]{}",
             controller.name, controller.bus_num, irq_str
         );
+        if let Some(power) = &controller.power {
+            println!("  power: {}", power.describe());
+        }
 
         // Print HID devices under this controller
         for device in &controller.hid_devices {
             let irq_str = device
                 .gpio_irq
                 .as_ref()
-                .map(|i| format!("IRQ {}", i))
+                .map(|i| describe_irq(&topology, i))
                 .unwrap_or_else(|| "no IRQ".to_string());
 
             println!(
@@ -112,9 +546,20 @@ fn cmd_list() -> Result<()> {
                 println!("    - {}", input_name);
             }
 
+            if !device.collections.is_empty() {
+                let collections: Vec<_> = device.collections.iter().map(|c| c.describe()).collect();
+                println!("    collections: {}", collections.join(", "));
+            }
+            if let Some(size) = device.max_input_report {
+                println!("    max input report: {} bytes", size);
+            }
+
             if !device.driver.is_empty() {
                 println!("    driver: {}", device.driver);
             }
+            if let Some(power) = &device.power {
+                println!("    power: {}", power.describe());
+            }
         }
         println!();
     }
@@ -123,90 +568,182 @@ fn cmd_list() -> Result<()> {
     Ok(())
 }
 
-fn cmd_monitor(interval_ms: u64, count: u32, threshold: f64) -> Result<()> {
-    let topology = discovery::discover()?;
+/// Describe an IRQ with its chip details (e.g., "IRQ 203, intel-gpio 18-edge").
+fn describe_irq(topology: &discovery::I2cTopology, irq: &str) -> String {
+    match topology.irq_chips.get(irq) {
+        Some(chip) => format!("IRQ {}, {}", irq, chip.describe()),
+        None => format!("IRQ {}", irq),
+    }
+}
+
+fn cmd_monitor(
+    interval_ms: u64,
+    count: u32,
+    thresholds: Thresholds,
+    min_duration_s: f64,
+    format: Format,
+    sample_source: &SampleSource,
+    roots: &FsRoots,
+) -> Result<()> {
+    let (topology, mut sampler) = sample_source.open(roots)?;
     let sources = topology.all_sources();
 
     if sources.is_empty() {
-        println!("No I2C-related interrupt sources found.");
+        if format == Format::Text {
+            println!("No I2C-related interrupt sources found.");
+        } else {
+            // Keep stdout a valid, empty document for the consumer of the output
+            eprintln!("No I2C-related interrupt sources found.");
+            output::SampleWriter::new(format).finish()?;
+        }
         return Ok(());
     }
 
-    println!("=== I2C Interrupt Rate Monitor ===");
-    println!(
-        "Interval: {}ms | Threshold: {:.0} irqs/s | Sources: {}",
-        interval_ms,
-        threshold,
-        sources.len()
-    );
-    println!();
+    // JSON is a single array, so it needs an end
+    if format == Format::Json && count == 0 && sampler.is_live() {
+        anyhow::bail!("--format json needs --count; use jsonl for unbounded output");
+    }
 
-    // Show discovered sources
-    for source in &sources {
-        let prefix = if source.is_controller {
-            ""
-        } else {
-            "  └─ "
-        };
+    let text = format == Format::Text;
+    let mut writer = output::SampleWriter::new(format);
+
+    if text {
+        println!("=== I2C Interrupt Rate Monitor ===");
         println!(
-            "{}IRQ {:>3}: {} ({})",
-            prefix, source.irq, source.name, source.device_type
+            "Interval: {}ms | Threshold: {:.0} irqs/s | Sources: {}",
+            interval_ms,
+            thresholds.default_warn,
+            sources.len()
         );
+        println!();
+
+        // Show discovered sources, with their limits where a rule sets them
+        for source in &sources {
+            let prefix = if source.is_controller {
+                ""
+            } else {
+                "  └─ "
+            };
+            let limit = thresholds.limit_for(source);
+            let limit = if limit.warn == thresholds.default_warn && limit.critical.is_none() {
+                String::new()
+            } else {
+                format!(" [limit {}]", limit)
+            };
+            println!(
+                "{}IRQ {:>3}: {} ({}){}",
+                prefix, source.irq, source.name, source.device_type, limit
+            );
+        }
+        println!();
     }
-    println!();
 
     // Build initial counts
-    let initial = interrupts::read_interrupts()?;
-    let mut prev_counts: HashMap<String, u64> =
-        initial.iter().map(|s| (s.irq.clone(), s.count)).collect();
-
-    // Print header
-    print!("{:>6}", "Sample");
-    for source in &sources {
-        let name = if source.name.len() > 18 {
-            format!("{}...", &source.name[..15])
-        } else {
-            source.name.clone()
-        };
-        print!("  {:>18}", name);
+    let initial = sampler
+        .sample()?
+        .context("no interrupt snapshots available")?;
+    let mut tracker = RateTracker::new(&sources, &initial, interval_ms);
+
+    if text {
+        // Print header
+        print!("{:>6}", "Sample");
+        for source in &sources {
+            let name = if source.name.len() > 18 {
+                format!("{}...", &source.name[..15])
+            } else {
+                source.name.clone()
+            };
+            print!("  {:>18}", name);
+        }
+        println!("  {:>10}", "Status");
     }
-    println!("  {:>10}", "Status");
 
-    let interval = Duration::from_millis(interval_ms);
-    let interval_s = interval_ms as f64 / 1000.0;
+    let mut detector = detector::Detector::new(&sources, &thresholds, min_duration_s);
+    let mut levels: Vec<LevelTracker> = sources
+        .iter()
+        .map(|s| LevelTracker::new(thresholds.limit_for(s)))
+        .collect();
+    let mut schedule = Schedule::new(Duration::from_millis(interval_ms));
     let mut sample_num = 0u32;
 
     loop {
-        thread::sleep(interval);
-        sample_num += 1;
-
-        let current = interrupts::read_interrupts()?;
-        let current_map: HashMap<_, _> =
-            current.iter().map(|s| (s.irq.as_str(), s.count)).collect();
-
-        print!("{:>6}", sample_num);
-        let mut any_high = false;
-
-        for source in &sources {
-            let curr = current_map.get(source.irq.as_str()).copied().unwrap_or(0);
-            let prev = prev_counts.get(&source.irq).copied().unwrap_or(0);
-            let delta = curr.saturating_sub(prev);
-            let rate = delta as f64 / interval_s;
+        let missed_before = schedule.missed;
+        if sampler.is_live() {
+            schedule.wait();
+            schedule.advance(Instant::now());
+        }
 
-            prev_counts.insert(source.irq.clone(), curr);
+        let Some(current) = sampler.sample()? else {
+            break;
+        };
+        sample_num += 1;
 
-            let rate_str = format!("{:.1}/s", rate);
-            print!("  {:>18}", rate_str);
+        let (dt_s, rates) = match tracker.next(&current) {
+            Interval::Rates { dt_s, sources } => (dt_s, sources),
+            // An interval that spans a suspend has no meaningful rates
+            Interval::Suspended(gap) => {
+                if text {
+                    println!("{:>6}  -- {} --", sample_num, gap.describe());
+                } else {
+                    let mut record = output::SampleRecord::new(
+                        sample_num,
+                        current.timestamp,
+                        sampler.is_live(),
+                        Vec::new(),
+                    );
+                    record.suspend = Some(gap);
+                    writer.write(record)?;
+                }
+                if count > 0 && sample_num >= count {
+                    break;
+                }
+                continue;
+            }
+        };
+        let samples: Vec<_> = sources
+            .iter()
+            .zip(&rates)
+            .zip(&mut levels)
+            .map(|((source, r), level)| {
+                let level = level.update(r.rate, dt_s);
+                output::SourceSample::new(source, r.count, r.delta, r.rate, level)
+            })
+            .collect();
+        let events = detector.update(
+            current.timestamp - initial.timestamp,
+            dt_s,
+            &rates::by_irq(&sources, &rates),
+        );
 
-            if rate > threshold {
-                any_high = true;
+        if text {
+            print!("{:>6}", sample_num);
+            for sample in &samples {
+                let rate_str = format!("{:.1}/s", sample.rate);
+                print!("  {:>18}", rate_str);
             }
-        }
 
-        if any_high {
-            println!("  {:>10}", "** HIGH");
+            let status = samples
+                .iter()
+                .map(|sample| sample.level)
+                .max()
+                .unwrap_or_default()
+                .status();
+            match schedule.missed - missed_before {
+                0 => println!("  {:>10}", status),
+                missed => println!("  {:>10}  ({} missed)", status, missed),
+            }
+            for event in &events {
+                println!("{:>6}  ! {}", "", event.describe());
+            }
         } else {
-            println!("  {:>10}", "ok");
+            let mut record = output::SampleRecord::new(
+                sample_num,
+                current.timestamp,
+                sampler.is_live(),
+                samples,
+            );
+            record.events = events;
+            writer.write(record)?;
         }
 
         if count > 0 && sample_num >= count {
@@ -214,5 +751,61 @@ fn cmd_monitor(interval_ms: u64, count: u32, threshold: f64) -> Result<()> {
         }
     }
 
+    if text && sampler.is_live() {
+        println!(
+            "\nSamples: {} ({} late, {} missed)",
+            sample_num, schedule.late, schedule.missed
+        );
+    }
+
+    writer.finish()
+}
+
+fn cmd_record(
+    output: &Path,
+    interval_ms: u64,
+    count: u32,
+    backend: Backend,
+    roots: &FsRoots,
+) -> Result<()> {
+    let topology = discovery::discover(roots)?;
+    let sources = topology.all_sources();
+
+    if sources.is_empty() {
+        anyhow::bail!("No I2C-related interrupt sources found.");
+    }
+
+    let mut sampler = backend.open_with_power(&topology, roots);
+    let mut writer = recording::Writer::create(output, &Header::new(interval_ms, &topology))?;
+
+    println!(
+        "Recording {} sources every {}ms to {} (Ctrl-C to stop)",
+        sources.len(),
+        interval_ms,
+        output.display()
+    );
+
+    let mut schedule = Schedule::new(Duration::from_millis(interval_ms));
+    let mut written = 0u32;
+
+    // The initial snapshot is the baseline for the first rate, so record count + 1
+    loop {
+        let snapshot = sampler
+            .sample()?
+            .context("no interrupt snapshots available")?;
+        writer.write(&snapshot)?;
+
+        if count > 0 && written >= count {
+            break;
+        }
+        written += 1;
+        schedule.wait();
+        schedule.advance(Instant::now());
+    }
+
+    println!(
+        "Recorded {} samples ({} late, {} missed).",
+        written, schedule.late, schedule.missed
+    );
     Ok(())
 }
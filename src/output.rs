@@ -0,0 +1,432 @@
+//! Machine-readable output of `list` and `monitor`.
+
+use std::io::{self, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::detector::Event;
+use crate::discovery::{I2cTopology, InterruptSourceInfo};
+use crate::suspend::SuspendGap;
+use crate::thresholds::Level;
+
+/// Output format of `list` and `monitor`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Human-readable tables
+    Text,
+    /// A single JSON document
+    Json,
+    /// One JSON object per line
+    Jsonl,
+    /// Comma-separated values with a header row
+    Csv,
+}
+
+/// Quote a CSV field if needed (RFC 4180).
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn csv_line(fields: &[String]) -> String {
+    fields
+        .iter()
+        .map(|f| csv_field(f))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// One row per HID device (or per controller without devices).
+#[derive(Serialize)]
+struct DeviceRow<'a> {
+    controller: &'a str,
+    bus_num: u8,
+    controller_irq: Option<&'a str>,
+    controller_irq_chip: Option<String>,
+    acpi_name: Option<&'a str>,
+    vendor_id: Option<String>,
+    product_id: Option<String>,
+    device_type: Option<&'a str>,
+    driver: Option<&'a str>,
+    irq: Option<&'a str>,
+    irq_chip: Option<String>,
+    input_names: Vec<&'a str>,
+    collections: Vec<String>,
+    max_input_report: Option<usize>,
+}
+
+const DEVICE_CSV_HEADER: &[&str] = &[
+    "controller",
+    "bus_num",
+    "controller_irq",
+    "controller_irq_chip",
+    "acpi_name",
+    "vendor_id",
+    "product_id",
+    "device_type",
+    "driver",
+    "irq",
+    "irq_chip",
+    "input_names",
+    "collections",
+    "max_input_report",
+];
+
+impl DeviceRow<'_> {
+    fn csv_fields(&self) -> Vec<String> {
+        let opt = |v: Option<&str>| v.unwrap_or_default().to_string();
+        vec![
+            self.controller.to_string(),
+            self.bus_num.to_string(),
+            opt(self.controller_irq),
+            opt(self.controller_irq_chip.as_deref()),
+            opt(self.acpi_name),
+            opt(self.vendor_id.as_deref()),
+            opt(self.product_id.as_deref()),
+            opt(self.device_type),
+            opt(self.driver),
+            opt(self.irq),
+            opt(self.irq_chip.as_deref()),
+            self.input_names.join(";"),
+            self.collections.join(";"),
+            self.max_input_report
+                .map(|n| n.to_string())
+                .unwrap_or_default(),
+        ]
+    }
+}
+
+fn device_rows(topology: &I2cTopology) -> Vec<DeviceRow<'_>> {
+    let chip = |irq: Option<&String>| {
+        irq.and_then(|irq| topology.irq_chips.get(irq))
+            .map(|c| c.describe())
+    };
+    let mut rows = Vec::new();
+
+    for controller in &topology.controllers {
+        let row = DeviceRow {
+            controller: &controller.name,
+            bus_num: controller.bus_num,
+            controller_irq: controller.irq.as_deref(),
+            controller_irq_chip: chip(controller.irq.as_ref()),
+            acpi_name: None,
+            vendor_id: None,
+            product_id: None,
+            device_type: None,
+            driver: None,
+            irq: None,
+            irq_chip: None,
+            input_names: Vec::new(),
+            collections: Vec::new(),
+            max_input_report: None,
+        };
+        if controller.hid_devices.is_empty() {
+            rows.push(row);
+            continue;
+        }
+        for device in &controller.hid_devices {
+            rows.push(DeviceRow {
+                acpi_name: Some(&device.acpi_name),
+                vendor_id: Some(format!("{:04x}", device.vendor_id)),
+                product_id: Some(format!("{:04x}", device.product_id)),
+                device_type: Some(&device.device_type),
+                driver: Some(&device.driver),
+                irq: device.gpio_irq.as_deref(),
+                irq_chip: chip(device.gpio_irq.as_ref()),
+                input_names: device.input_names.iter().map(String::as_str).collect(),
+                collections: device.collections.iter().map(|c| c.describe()).collect(),
+                max_input_report: device.max_input_report,
+                controller_irq_chip: row.controller_irq_chip.clone(),
+                ..row
+            });
+        }
+    }
+
+    rows
+}
+
+/// Render the topology in a machine-readable format.
+///
+/// JSON serializes the full topology; JSON Lines and CSV flatten it to one
+/// row per HID device.
+pub fn render_topology(topology: &I2cTopology, format: Format) -> Result<String> {
+    let mut out = String::new();
+    match format {
+        Format::Text => unreachable!("text output is printed by the command"),
+        Format::Json => {
+            out.push_str(&serde_json::to_string_pretty(topology)?);
+            out.push('\n');
+        }
+        Format::Jsonl => {
+            for row in device_rows(topology) {
+                out.push_str(&serde_json::to_string(&row)?);
+                out.push('\n');
+            }
+        }
+        Format::Csv => {
+            out.push_str(&DEVICE_CSV_HEADER.join(","));
+            out.push('\n');
+            for row in device_rows(topology) {
+                out.push_str(&csv_line(&row.csv_fields()));
+                out.push('\n');
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Counts of one source in one monitor sample.
+#[derive(Debug, Serialize)]
+pub struct SourceSample {
+    pub irq: String,
+    pub name: String,
+    pub controller: String,
+    pub device_type: String,
+    /// Raw counter value summed over all CPUs
+    pub count: u64,
+    /// Interrupts since the previous sample
+    pub delta: u64,
+    /// Interrupts per second
+    pub rate: f64,
+    /// Level of the source's limit after this sample
+    pub level: Level,
+}
+
+impl SourceSample {
+    pub fn new(
+        source: &InterruptSourceInfo,
+        count: u64,
+        delta: u64,
+        rate: f64,
+        level: Level,
+    ) -> Self {
+        Self {
+            irq: source.irq.clone(),
+            name: source.name.clone(),
+            controller: source.controller.clone(),
+            device_type: source.device_type.clone(),
+            count,
+            delta,
+            rate,
+            level,
+        }
+    }
+}
+
+/// One monitor sample.
+#[derive(Debug, Serialize)]
+pub struct SampleRecord {
+    /// Sample number, starting at 1
+    pub sample: u32,
+    /// Seconds since sampling started
+    pub timestamp: f64,
+    /// Wall-clock time in seconds since the Unix epoch (live sampling only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unix_time: Option<f64>,
+    /// Set for intervals that spanned a system suspend, which carry no rates
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suspend: Option<SuspendGap>,
+    pub sources: Vec<SourceSample>,
+    /// Storm and stuck-source conditions that started or ended in this sample
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<Event>,
+}
+
+impl SampleRecord {
+    pub fn new(sample: u32, timestamp: f64, live: bool, sources: Vec<SourceSample>) -> Self {
+        let unix_time = live
+            .then(|| SystemTime::now().duration_since(UNIX_EPOCH).ok())
+            .flatten()
+            .map(|d| d.as_secs_f64());
+        Self {
+            sample,
+            timestamp,
+            unix_time,
+            suspend: None,
+            sources,
+            events: Vec::new(),
+        }
+    }
+}
+
+const SAMPLE_CSV_HEADER: &[&str] = &[
+    "sample",
+    "timestamp",
+    "unix_time",
+    "irq",
+    "name",
+    "controller",
+    "device_type",
+    "count",
+    "delta",
+    "rate",
+    "level",
+    "events",
+];
+
+/// Writes monitor samples to stdout as they arrive.
+///
+/// JSON output is a single array, so it is printed once sampling ends.
+pub struct SampleWriter {
+    format: Format,
+    header_written: bool,
+    records: Vec<SampleRecord>,
+}
+
+impl SampleWriter {
+    pub fn new(format: Format) -> Self {
+        Self {
+            format,
+            header_written: false,
+            records: Vec::new(),
+        }
+    }
+
+    pub fn write(&mut self, record: SampleRecord) -> Result<()> {
+        // Return errors (e.g., a closed pipe) instead of panicking in print!
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(self.render(record)?.as_bytes())?;
+        stdout.flush()?;
+        Ok(())
+    }
+
+    fn render(&mut self, record: SampleRecord) -> Result<String> {
+        let mut out = String::new();
+        match self.format {
+            Format::Text => unreachable!("text output is printed by the command"),
+            Format::Json => self.records.push(record),
+            Format::Jsonl => {
+                out.push_str(&serde_json::to_string(&record)?);
+                out.push('\n');
+            }
+            Format::Csv => {
+                if !self.header_written {
+                    out.push_str(&SAMPLE_CSV_HEADER.join(","));
+                    out.push('\n');
+                    self.header_written = true;
+                }
+                let unix_time = record.unix_time.map(|t| t.to_string()).unwrap_or_default();
+                for source in &record.sources {
+                    let events: Vec<String> = record
+                        .events
+                        .iter()
+                        .filter(|e| e.irq == source.irq)
+                        .map(Event::describe)
+                        .collect();
+                    out.push_str(&csv_line(&[
+                        record.sample.to_string(),
+                        record.timestamp.to_string(),
+                        unix_time.clone(),
+                        source.irq.clone(),
+                        source.name.clone(),
+                        source.controller.clone(),
+                        source.device_type.clone(),
+                        source.count.to_string(),
+                        source.delta.to_string(),
+                        source.rate.to_string(),
+                        source.level.name().to_string(),
+                        events.join("; "),
+                    ]));
+                    out.push('\n');
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    /// Print anything held back until the end (the JSON array).
+    ///
+    /// Output without any samples is still a valid document: `[]` for JSON
+    /// and the header row for CSV.
+    pub fn finish(self) -> Result<()> {
+        print!("{}", self.render_end()?);
+        Ok(())
+    }
+
+    fn render_end(&self) -> Result<String> {
+        Ok(match self.format {
+            Format::Json => format!("{}\n", serde_json::to_string_pretty(&self.records)?),
+            Format::Csv if !self.header_written => format!("{}\n", SAMPLE_CSV_HEADER.join(",")),
+            _ => String::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::discovery::IrqChipInfo;
+    use crate::fixture;
+
+    fn topology() -> I2cTopology {
+        let mut topology = fixture::touchpad_topology();
+        topology.controllers[0].hid_devices[0].input_names =
+            vec!["Touchpad".to_string(), "Mouse, Keys".to_string()];
+        topology.irq_chips = HashMap::from([(
+            "203".to_string(),
+            IrqChipInfo {
+                chip: "intel-gpio".to_string(),
+                hwirq: Some("18".to_string()),
+                trigger: Some("edge".to_string()),
+            },
+        )]);
+        topology
+    }
+
+    #[test]
+    fn test_render_topology_csv() {
+        let csv = render_topology(&topology(), Format::Csv).unwrap();
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[1],
+            "i2c_designware.5,5,21,,PIXA3854:00,093a,0274,Touchpad,hid-multitouch,203,intel-gpio 18-edge,\"Touchpad;Mouse, Keys\",,"
+        );
+    }
+
+    #[test]
+    fn test_render_topology_json_round_trips() {
+        let json = render_topology(&topology(), Format::Json).unwrap();
+        let parsed: I2cTopology = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.controllers[0].hid_devices[0].vendor_id, 0x093A);
+    }
+
+    #[test]
+    fn test_sample_writer_csv() {
+        let source = &topology().all_sources()[1];
+        let mut writer = SampleWriter::new(Format::Csv);
+        let record = SampleRecord::new(
+            1,
+            1.0,
+            false,
+            vec![SourceSample::new(source, 137, 37, 37.0, Level::Ok)],
+        );
+        let out = writer.render(record).unwrap();
+        assert_eq!(
+            out,
+            format!(
+                "{}\n1,1,,203,PIXA3854:00,i2c_designware.5,Touchpad,137,37,37,ok,\n",
+                SAMPLE_CSV_HEADER.join(",")
+            )
+        );
+    }
+
+    #[test]
+    fn test_sample_writer_without_samples() {
+        let end = |format| SampleWriter::new(format).render_end().unwrap();
+        assert_eq!(end(Format::Json), "[]\n");
+        assert_eq!(end(Format::Jsonl), "");
+        assert_eq!(
+            end(Format::Csv),
+            format!("{}\n", SAMPLE_CSV_HEADER.join(","))
+        );
+    }
+}
@@ -0,0 +1,170 @@
+//! Runtime PM and wakeup state from sysfs `power/` directories.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::discovery::I2cTopology;
+use crate::sampler::{Sampler, Snapshot};
+
+/// A device's `power/` attributes; each is `None` where the kernel does not expose it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PowerState {
+    /// Runtime PM status (e.g., "active", "suspended", "unsupported")
+    pub runtime_status: Option<String>,
+    /// Time spent runtime-active since boot, in milliseconds
+    pub runtime_active_time: Option<u64>,
+    /// Time spent runtime-suspended since boot, in milliseconds
+    pub runtime_suspended_time: Option<u64>,
+    /// Runtime PM control ("auto" allows runtime suspend, "on" keeps the device active)
+    pub control: Option<String>,
+    /// Whether the device may wake the system ("enabled" or "disabled")
+    pub wakeup: Option<String>,
+    /// Wakeup events signaled by the device
+    pub wakeup_count: Option<u64>,
+}
+
+impl PowerState {
+    /// Read the `power/` directory of a sysfs device, `None` if it has none.
+    pub fn read(device_dir: &Path) -> Option<Self> {
+        let dir = device_dir.join("power");
+        if !dir.is_dir() {
+            return None;
+        }
+        let attr = |name: &str| {
+            let value = fs::read_to_string(dir.join(name)).ok()?;
+            let value = value.trim();
+            (!value.is_empty()).then(|| value.to_string())
+        };
+        let number = |name: &str| attr(name)?.parse().ok();
+        Some(Self {
+            runtime_status: attr("runtime_status"),
+            runtime_active_time: number("runtime_active_time"),
+            runtime_suspended_time: number("runtime_suspended_time"),
+            control: attr("control"),
+            wakeup: attr("wakeup"),
+            wakeup_count: number("wakeup_count"),
+        })
+    }
+
+    /// One-line description (e.g., "suspended, control auto, wakeup enabled (3)").
+    pub fn describe(&self) -> String {
+        let mut parts = vec![
+            self.runtime_status
+                .as_deref()
+                .unwrap_or("unknown")
+                .to_string(),
+        ];
+        if let Some(control) = &self.control {
+            parts.push(format!("control {}", control));
+        }
+        match (&self.wakeup, self.wakeup_count) {
+            (Some(wakeup), Some(count)) => parts.push(format!("wakeup {} ({})", wakeup, count)),
+            (Some(wakeup), None) => parts.push(format!("wakeup {}", wakeup)),
+            _ => {}
+        }
+        parts.join(", ")
+    }
+}
+
+/// Share of the time between two states the device spent runtime-active, in percent.
+///
+/// `None` if the times are unavailable or did not advance (runtime PM
+/// disabled or unsupported).
+pub fn active_percent(before: &PowerState, after: &PowerState) -> Option<f64> {
+    let active = after
+        .runtime_active_time?
+        .checked_sub(before.runtime_active_time?)?;
+    let suspended = after
+        .runtime_suspended_time?
+        .checked_sub(before.runtime_suspended_time?)?;
+    let total = active + suspended;
+    (total > 0).then(|| active as f64 * 100.0 / total as f64)
+}
+
+/// Sysfs directories of the controllers and HID devices of a topology.
+///
+/// Keyed like `InterruptSourceInfo::device`: the controller name for
+/// controllers, the I2C client name for HID devices.
+pub fn device_dirs(topology: &I2cTopology) -> Vec<(String, PathBuf)> {
+    let mut dirs = Vec::new();
+    for controller in &topology.controllers {
+        if let Some(dir) = &controller.sysfs_dir {
+            dirs.push((controller.name.clone(), dir.clone()));
+        }
+        for device in &controller.hid_devices {
+            if let Some(dir) = &device.sysfs_dir {
+                dirs.push((device.client.clone(), dir.clone()));
+            }
+        }
+    }
+    dirs
+}
+
+/// Adds the power state of each device to the snapshots of another sampler.
+pub struct PowerSampler {
+    inner: Box<dyn Sampler>,
+    devices: Vec<(String, PathBuf)>,
+}
+
+impl PowerSampler {
+    pub fn new(inner: Box<dyn Sampler>, devices: Vec<(String, PathBuf)>) -> Self {
+        Self { inner, devices }
+    }
+}
+
+impl Sampler for PowerSampler {
+    fn sample(&mut self) -> Result<Option<Snapshot>> {
+        let Some(mut snapshot) = self.inner.sample()? else {
+            return Ok(None);
+        };
+        snapshot.power = self
+            .devices
+            .iter()
+            .filter_map(|(key, dir)| Some((key.clone(), PowerState::read(dir)?)))
+            .collect::<HashMap<_, _>>();
+        Ok(Some(snapshot))
+    }
+
+    fn is_live(&self) -> bool {
+        self.inner.is_live()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+
+    #[test]
+    fn test_read_power_state() {
+        let fixture = Fixture::new();
+        fixture
+            .file("sys/devices/dev/power/runtime_status", "suspended\n")
+            .file("sys/devices/dev/power/runtime_active_time", "1200\n")
+            .file("sys/devices/dev/power/runtime_suspended_time", "800\n")
+            .file("sys/devices/dev/power/control", "auto\n")
+            .file("sys/devices/dev/power/wakeup", "enabled\n")
+            .file("sys/devices/dev/power/wakeup_count", "\n")
+            .file("sys/devices/nopm/name", "x\n");
+
+        let devices = fixture.roots().sys_path("devices");
+        let before = PowerState::read(&devices.join("dev")).unwrap();
+        assert_eq!(before.runtime_status.as_deref(), Some("suspended"));
+        assert_eq!(before.wakeup_count, None);
+        assert_eq!(before.describe(), "suspended, control auto, wakeup enabled");
+        assert_eq!(PowerState::read(&devices.join("nopm")), None);
+
+        let after = PowerState {
+            runtime_active_time: Some(1950),
+            runtime_suspended_time: Some(1050),
+            ..before.clone()
+        };
+        assert_eq!(active_percent(&before, &after), Some(75.0));
+        assert_eq!(active_percent(&before, &before), None);
+        assert_eq!(active_percent(&PowerState::default(), &after), None);
+    }
+}
@@ -0,0 +1,169 @@
+//! RAPL energy counters from /sys/class/powercap/intel-rapl:*.
+
+use std::fs;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::roots::FsRoots;
+use crate::sampler::{Sampler, Snapshot};
+
+/// Energy counter of one RAPL zone or sub-zone.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnergyZone {
+    /// Zone directory (e.g., "intel-rapl:0", or "intel-rapl:0:0" for a sub-zone)
+    pub zone: String,
+    /// Zone name (e.g., "package-0", "core", "uncore", "psys")
+    pub name: String,
+    /// Energy counter in microjoules (`energy_uj`)
+    pub energy_uj: u64,
+    /// Counter range; it wraps to 0 after this value (`max_energy_range_uj`)
+    pub max_energy_range_uj: Option<u64>,
+}
+
+/// Average power of one zone between two snapshots.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ZonePower {
+    pub zone: String,
+    pub name: String,
+    pub watts: f64,
+}
+
+/// Read every RAPL zone and sub-zone, sorted by zone directory.
+///
+/// Returns an empty list if RAPL is unavailable or `energy_uj` is not
+/// readable (it is root-only on current kernels).
+pub fn read_zones(roots: &FsRoots) -> Vec<EnergyZone> {
+    let Ok(entries) = fs::read_dir(roots.sys_path("class/powercap")) else {
+        return Vec::new();
+    };
+
+    let mut zones: Vec<EnergyZone> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let zone = entry.file_name().to_str()?.to_string();
+            if !zone.starts_with("intel-rapl:") {
+                return None;
+            }
+            let dir = entry.path();
+            let read = |name: &str| fs::read_to_string(dir.join(name)).ok();
+            Some(EnergyZone {
+                name: read("name").map_or_else(|| zone.clone(), |n| n.trim().to_string()),
+                energy_uj: read("energy_uj")?.trim().parse().ok()?,
+                max_energy_range_uj: read("max_energy_range_uj")
+                    .and_then(|m| m.trim().parse().ok()),
+                zone,
+            })
+        })
+        .collect();
+    zones.sort_by(|a, b| a.zone.cmp(&b.zone));
+    zones
+}
+
+/// Energy consumed between two readings of a counter, in microjoules.
+///
+/// A counter that went backwards wrapped around once at `max_range`;
+/// without a known range the interval is dropped.
+pub fn energy_delta(before: u64, after: u64, max_range: Option<u64>) -> Option<u64> {
+    if after >= before {
+        Some(after - before)
+    } else {
+        Some(max_range?.checked_sub(before)? + after)
+    }
+}
+
+/// Average power of each zone over `dt_s` seconds.
+pub fn power(before: &[EnergyZone], after: &[EnergyZone], dt_s: f64) -> Vec<ZonePower> {
+    if dt_s <= 0.0 {
+        return Vec::new();
+    }
+    after
+        .iter()
+        .filter_map(|zone| {
+            let prev = before.iter().find(|z| z.zone == zone.zone)?;
+            let delta = energy_delta(prev.energy_uj, zone.energy_uj, zone.max_energy_range_uj)?;
+            Some(ZonePower {
+                zone: zone.zone.clone(),
+                name: zone.name.clone(),
+                watts: delta as f64 / 1e6 / dt_s,
+            })
+        })
+        .collect()
+}
+
+/// Adds RAPL energy counters to the snapshots of another sampler.
+pub struct RaplSampler {
+    inner: Box<dyn Sampler>,
+    roots: FsRoots,
+}
+
+impl RaplSampler {
+    pub fn new(inner: Box<dyn Sampler>, roots: FsRoots) -> Self {
+        Self { inner, roots }
+    }
+}
+
+impl Sampler for RaplSampler {
+    fn sample(&mut self) -> Result<Option<Snapshot>> {
+        let Some(mut snapshot) = self.inner.sample()? else {
+            return Ok(None);
+        };
+        snapshot.energy = read_zones(&self.roots);
+        Ok(Some(snapshot))
+    }
+
+    fn is_live(&self) -> bool {
+        self.inner.is_live()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+
+    #[test]
+    fn test_read_zones() {
+        let fixture = Fixture::new();
+        let zone = |dir: &str, name: &str, energy: &str| {
+            fixture
+                .file(
+                    &format!("sys/class/powercap/{dir}/name"),
+                    format!("{name}\n"),
+                )
+                .file(&format!("sys/class/powercap/{dir}/energy_uj"), energy)
+                .file(
+                    &format!("sys/class/powercap/{dir}/max_energy_range_uj"),
+                    "262143328850\n",
+                );
+        };
+        zone("intel-rapl:0", "package-0", "1000000\n");
+        zone("intel-rapl:0:0", "core", "400000\n");
+        zone("intel-rapl-mmio:0", "package-0", "7\n");
+        fixture.file("sys/class/powercap/intel-rapl:1/name", "psys\n");
+
+        let zones = read_zones(&fixture.roots());
+        let names: Vec<_> = zones.iter().map(|z| z.zone.as_str()).collect();
+        assert_eq!(names, ["intel-rapl:0", "intel-rapl:0:0"]);
+        assert_eq!(zones[0].name, "package-0");
+        assert_eq!(zones[1].name, "core");
+        assert_eq!(zones[1].max_energy_range_uj, Some(262_143_328_850));
+    }
+
+    #[test]
+    fn test_power_handles_wraparound() {
+        assert_eq!(energy_delta(100, 350, None), Some(250));
+        assert_eq!(energy_delta(900, 100, Some(1000)), Some(200));
+        assert_eq!(energy_delta(900, 100, None), None);
+
+        let zone = |energy_uj| EnergyZone {
+            zone: "intel-rapl:0".to_string(),
+            name: "package-0".to_string(),
+            energy_uj,
+            max_energy_range_uj: Some(262_143_328_850),
+        };
+        // 1.5 J across the wrap in 0.5 s
+        let power = power(&[zone(262_142_828_850)], &[zone(1_000_000)], 0.5);
+        assert_eq!(power[0].watts, 3.0);
+    }
+}
@@ -0,0 +1,149 @@
+//! Per-interval interrupt rates from consecutive snapshots.
+
+use std::collections::HashMap;
+
+use crate::discovery::InterruptSourceInfo;
+use crate::sampler::Snapshot;
+use crate::suspend::{self, SuspendGap, SuspendState};
+
+/// Counts of one source over one interval.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SourceRate {
+    /// Raw counter value summed over all CPUs
+    pub count: u64,
+    /// Interrupts since the previous snapshot
+    pub delta: u64,
+    /// Interrupts per second
+    pub rate: f64,
+}
+
+/// What happened between two snapshots.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Interval {
+    /// Rates of every source, in the order the tracker was created with
+    Rates { dt_s: f64, sources: Vec<SourceRate> },
+    /// The interval spanned a system suspend, so its counts carry no rates
+    Suspended(SuspendGap),
+}
+
+/// Turns snapshots into per-interval rates of a fixed set of sources.
+pub struct RateTracker {
+    irqs: Vec<String>,
+    prev_counts: HashMap<String, u64>,
+    prev_timestamp: f64,
+    prev_suspend: Option<SuspendState>,
+    /// Interval assumed when snapshots carry no usable timestamps, in seconds
+    fallback_dt_s: f64,
+}
+
+impl RateTracker {
+    pub fn new(sources: &[InterruptSourceInfo], initial: &Snapshot, interval_ms: u64) -> Self {
+        Self {
+            irqs: sources.iter().map(|s| s.irq.clone()).collect(),
+            prev_counts: sources
+                .iter()
+                .filter_map(|s| Some((s.irq.clone(), initial.total(&s.irq)?)))
+                .collect(),
+            prev_timestamp: initial.timestamp,
+            prev_suspend: initial.suspend.clone(),
+            fallback_dt_s: interval_ms as f64 / 1000.0,
+        }
+    }
+
+    /// Rates since the previous snapshot.
+    ///
+    /// Rates use the measured time between snapshots, not the nominal interval.
+    pub fn next(&mut self, snapshot: &Snapshot) -> Interval {
+        let mut dt_s = snapshot.timestamp - self.prev_timestamp;
+        if dt_s <= 0.0 {
+            dt_s = self.fallback_dt_s;
+        }
+        self.prev_timestamp = snapshot.timestamp;
+
+        let gap = match (&self.prev_suspend, &snapshot.suspend) {
+            (Some(prev), Some(state)) => suspend::detect(prev, state),
+            _ => None,
+        };
+        self.prev_suspend = snapshot.suspend.clone();
+
+        let mut sources = Vec::with_capacity(self.irqs.len());
+        for irq in &self.irqs {
+            let count = snapshot.total(irq).unwrap_or(0);
+            let prev = self.prev_counts.get(irq).copied().unwrap_or(0);
+            let delta = count.saturating_sub(prev);
+            self.prev_counts.insert(irq.clone(), count);
+            sources.push(SourceRate {
+                count,
+                delta,
+                rate: delta as f64 / dt_s,
+            });
+        }
+
+        match gap {
+            Some(gap) => Interval::Suspended(gap),
+            None => Interval::Rates { dt_s, sources },
+        }
+    }
+}
+
+/// Rates keyed by IRQ, as the detector takes them.
+pub fn by_irq(sources: &[InterruptSourceInfo], rates: &[SourceRate]) -> HashMap<String, f64> {
+    sources
+        .iter()
+        .zip(rates)
+        .map(|(source, rate)| (source.irq.clone(), rate.rate))
+        .collect()
+}
+
+/// Interrupt totals and peak rates over a series of intervals.
+pub struct Totals {
+    pub sources: Vec<InterruptSourceInfo>,
+    pub deltas: Vec<u64>,
+    pub peaks: Vec<f64>,
+    pub elapsed_s: f64,
+    pub intervals: u32,
+}
+
+impl Totals {
+    pub fn new(sources: Vec<InterruptSourceInfo>) -> Self {
+        let len = sources.len();
+        Self {
+            sources,
+            deltas: vec![0; len],
+            peaks: vec![0.0; len],
+            elapsed_s: 0.0,
+            intervals: 0,
+        }
+    }
+
+    pub fn add(&mut self, dt_s: f64, rates: &[SourceRate]) {
+        for (idx, rate) in rates.iter().enumerate() {
+            self.deltas[idx] += rate.delta;
+            self.peaks[idx] = self.peaks[idx].max(rate.rate);
+        }
+        self.elapsed_s += dt_s;
+        self.intervals += 1;
+    }
+
+    /// Mean rate of a source over all intervals.
+    pub fn mean(&self, idx: usize) -> f64 {
+        self.deltas[idx] as f64 / self.elapsed_s.max(f64::EPSILON)
+    }
+
+    /// Controller IRQs per HID IRQ over all intervals, `None` while the HID devices were idle.
+    pub fn ratio(&self, controller: &str) -> Option<f64> {
+        let mut ctrl = 0;
+        let mut hid = 0;
+        for (source, delta) in self.sources.iter().zip(&self.deltas) {
+            if source.controller != controller {
+                continue;
+            }
+            if source.is_controller {
+                ctrl += delta;
+            } else {
+                hid += delta;
+            }
+        }
+        (hid > 0).then(|| ctrl as f64 / hid as f64)
+    }
+}
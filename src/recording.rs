@@ -0,0 +1,200 @@
+//! Recording file format for sampling sessions.
+//!
+//! A recording is a JSON Lines file: a header line identifying the format and
+//! its version, followed by one snapshot per line.
+
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::discovery::I2cTopology;
+use crate::sampler::Snapshot;
+
+/// Format identifier in the header line.
+pub const FORMAT_NAME: &str = "i2c-int-monitor-recording";
+
+/// Current recording format version.
+pub const FORMAT_VERSION: u32 = 2;
+
+/// First line of a recording.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Header {
+    /// Always FORMAT_NAME
+    pub format: String,
+    /// Format version the file was written with
+    pub version: u32,
+    /// Nominal sampling interval in milliseconds
+    #[serde(default)]
+    pub interval_ms: Option<u64>,
+    /// Topology discovered on the recording machine
+    #[serde(default)]
+    pub topology: Option<I2cTopology>,
+    /// What triggered the recording, for files written by `capture`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trigger: Option<TriggerInfo>,
+}
+
+/// Why and when a capture was triggered.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TriggerInfo {
+    /// Timestamp of the snapshot that fired the trigger
+    pub timestamp: f64,
+    /// The conditions that fired (e.g., "rate touchpad>500: PIXA3854:00 (IRQ 203) at 612.0/s")
+    pub reason: String,
+}
+
+impl Header {
+    pub fn new(interval_ms: u64, topology: &I2cTopology) -> Self {
+        Self {
+            format: FORMAT_NAME.to_string(),
+            version: FORMAT_VERSION,
+            interval_ms: Some(interval_ms),
+            topology: Some(topology.clone()),
+            trigger: None,
+        }
+    }
+}
+
+/// A recording loaded into memory.
+#[derive(Debug, Clone)]
+pub struct Recording {
+    pub header: Header,
+    pub snapshots: Vec<Snapshot>,
+}
+
+impl Recording {
+    /// Load a recording from a file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        Self::parse(&content).with_context(|| format!("invalid recording {}", path.display()))
+    }
+
+    /// Parse the content of a recording file.
+    fn parse(content: &str) -> Result<Self> {
+        let mut lines = content.lines().filter(|l| !l.trim().is_empty());
+
+        let header_line = lines.next().context("empty recording")?;
+        let header: Header =
+            serde_json::from_str(header_line).context("failed to parse recording header")?;
+        if header.format != FORMAT_NAME {
+            bail!("not a recording (format {:?})", header.format);
+        }
+        if header.version > FORMAT_VERSION {
+            bail!(
+                "recording version {} is newer than supported version {}",
+                header.version,
+                FORMAT_VERSION
+            );
+        }
+
+        let mut snapshots = Vec::new();
+        for (idx, line) in lines.enumerate() {
+            let snapshot: Snapshot = serde_json::from_str(line)
+                .with_context(|| format!("failed to parse snapshot {}", idx + 1))?;
+            snapshots.push(snapshot);
+        }
+
+        Ok(Self { header, snapshots })
+    }
+
+    /// Topology stored in the header, required to replay without live sysfs access.
+    pub fn topology(&self) -> Result<&I2cTopology> {
+        self.header
+            .topology
+            .as_ref()
+            .context("recording has no topology; record it with the 'record' command")
+    }
+}
+
+/// Writes a recording one line at a time.
+///
+/// Every line is flushed immediately, so an interrupted session still leaves a
+/// readable file behind.
+pub struct Writer {
+    out: BufWriter<File>,
+}
+
+impl Writer {
+    /// Create the file and write its header.
+    pub fn create(path: &Path, header: &Header) -> Result<Self> {
+        let file =
+            File::create(path).with_context(|| format!("failed to create {}", path.display()))?;
+        let mut writer = Self {
+            out: BufWriter::new(file),
+        };
+        writer.write_line(header)?;
+        Ok(writer)
+    }
+
+    /// Append a snapshot.
+    pub fn write(&mut self, snapshot: &Snapshot) -> Result<()> {
+        self.write_line(snapshot)
+    }
+
+    fn write_line(&mut self, value: &impl Serialize) -> Result<()> {
+        serde_json::to_writer(&mut self.out, value).context("failed to serialize recording")?;
+        self.out.write_all(b"\n")?;
+        self.out.flush().context("failed to write recording")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture;
+
+    #[test]
+    fn test_parse_recording() {
+        let content = r#"{"format":"i2c-int-monitor-recording","version":2}
+{"timestamp":0.0,"counts":{"42":[[0,10],[2,5]],"203":[[0,0],[2,7]]}}
+{"timestamp":1.002,"counts":{"42":[[0,110],[2,5]],"203":[[0,0],[2,12]]}}
+"#;
+        let recording = Recording::parse(content).unwrap();
+        assert_eq!(recording.header.version, 2);
+        assert!(recording.topology().is_err());
+        assert_eq!(recording.snapshots.len(), 2);
+        assert_eq!(recording.snapshots[1].total("42"), Some(115));
+        assert_eq!(recording.snapshots[1].counts["42"], vec![(0, 110), (2, 5)]);
+    }
+
+    #[test]
+    fn test_write_and_load_recording() {
+        let topology = I2cTopology {
+            controllers: Vec::new(),
+            gpio_irqs: Default::default(),
+            controller_irqs: [("i2c_designware.5".to_string(), "21".to_string())].into(),
+            irq_chips: Default::default(),
+        };
+        let snapshot = fixture::snapshot(0.5, &[("21", &[(0, 1), (2, 2), (3, 3)])]);
+
+        let path = std::env::temp_dir().join(format!(
+            "i2c-int-monitor-recording-{}.jsonl",
+            std::process::id()
+        ));
+        let mut writer = Writer::create(&path, &Header::new(250, &topology)).unwrap();
+        writer.write(&snapshot).unwrap();
+        drop(writer);
+
+        let recording = Recording::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(recording.header.interval_ms, Some(250));
+        assert_eq!(
+            recording.topology().unwrap().controller_irqs["i2c_designware.5"],
+            "21"
+        );
+        assert_eq!(recording.snapshots[0].counts["21"], snapshot.counts["21"]);
+        assert_eq!(recording.snapshots[0].total("21"), Some(6));
+    }
+
+    #[test]
+    fn test_parse_recording_rejects_newer_version() {
+        let content = r#"{"format":"i2c-int-monitor-recording","version":99}"#;
+        assert!(Recording::parse(content).is_err());
+    }
+}
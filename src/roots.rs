@@ -0,0 +1,36 @@
+//! Root directories of the proc and sys filesystems.
+
+use std::path::PathBuf;
+
+/// Where procfs and sysfs are mounted.
+///
+/// Every filesystem access goes through these roots, so the tool can be
+/// pointed at a tree captured from another machine or at a test fixture.
+#[derive(Debug, Clone)]
+pub struct FsRoots {
+    /// procfs root (normally /proc)
+    pub proc: PathBuf,
+    /// sysfs root (normally /sys)
+    pub sys: PathBuf,
+}
+
+impl Default for FsRoots {
+    fn default() -> Self {
+        Self {
+            proc: PathBuf::from("/proc"),
+            sys: PathBuf::from("/sys"),
+        }
+    }
+}
+
+impl FsRoots {
+    /// Path of a file below the procfs root (e.g., "interrupts").
+    pub fn proc_path(&self, relative: &str) -> PathBuf {
+        self.proc.join(relative)
+    }
+
+    /// Path of a file below the sysfs root (e.g., "bus/hid/devices").
+    pub fn sys_path(&self, relative: &str) -> PathBuf {
+        self.sys.join(relative)
+    }
+}
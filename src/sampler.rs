@@ -0,0 +1,295 @@
+//! Timestamped interrupt count snapshots from pluggable backends.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Instant;
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::cpuidle::{IdleSampler, IdleState};
+use crate::discovery::{self, I2cTopology};
+use crate::interrupts;
+use crate::power::{self, PowerSampler, PowerState};
+use crate::rapl::{EnergyZone, RaplSampler};
+use crate::recording::Recording;
+use crate::roots::FsRoots;
+use crate::suspend::{SuspendSampler, SuspendState};
+
+/// (CPU id, count) pairs keyed by IRQ number.
+pub type IrqCounts = HashMap<String, Vec<(u32, u64)>>;
+
+/// Per-CPU interrupt counts at one point in time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// Seconds since sampling started (monotonic)
+    pub timestamp: f64,
+    /// (CPU id, count) pairs keyed by IRQ number
+    pub counts: IrqCounts,
+    /// Runtime PM state keyed by device (controller name or I2C client)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub power: HashMap<String, PowerState>,
+    /// CPU idle-state counters, summed over all CPUs
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub idle: Vec<IdleState>,
+    /// RAPL energy counters
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub energy: Vec<EnergyZone>,
+    /// System suspend state, for detecting intervals that span a suspend
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suspend: Option<SuspendState>,
+}
+
+impl Snapshot {
+    /// Total count across all CPUs for an IRQ.
+    pub fn total(&self, irq: &str) -> Option<u64> {
+        self.counts
+            .get(irq)
+            .map(|per_cpu| per_cpu.iter().map(|&(_, count)| count).sum())
+    }
+}
+
+/// A source of interrupt count snapshots.
+pub trait Sampler {
+    /// Take the next snapshot, or `None` once the source is exhausted.
+    fn sample(&mut self) -> Result<Option<Snapshot>>;
+
+    /// Whether snapshots are taken live, so callers must wait between samples.
+    fn is_live(&self) -> bool {
+        true
+    }
+}
+
+/// Backend used for live sampling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Backend {
+    /// Parse /proc/interrupts (all IRQs in one read)
+    Procfs,
+    /// Read /sys/kernel/irq/<n>/per_cpu_count (only the monitored IRQs)
+    Sysfs,
+}
+
+/// Where snapshots come from.
+#[derive(Debug, Clone)]
+pub enum SampleSource {
+    /// Live counters read through a backend
+    Live(Backend),
+    /// Snapshots replayed from a recording file
+    Replay(PathBuf),
+}
+
+impl SampleSource {
+    /// Get the topology and open a sampler for its interrupt sources.
+    ///
+    /// Live sources discover the topology from the roots; replays use the
+    /// topology stored in the recording and never touch sysfs.
+    pub fn open(&self, roots: &FsRoots) -> Result<(I2cTopology, Box<dyn Sampler>)> {
+        match self {
+            SampleSource::Live(backend) => {
+                let topology = discovery::discover(roots)?;
+                let sampler = backend.open(&topology, roots);
+                Ok((topology, sampler))
+            }
+            SampleSource::Replay(path) => {
+                let recording = Recording::load(path)?;
+                let topology = recording.topology()?.clone();
+                Ok((topology, Box::new(ReplaySampler::new(recording.snapshots))))
+            }
+        }
+    }
+}
+
+impl Backend {
+    /// Open a live sampler for the interrupt sources of a topology.
+    ///
+    /// Snapshots carry the interrupt counts and the suspend counters needed
+    /// to skip intervals that span a suspend, and nothing else.
+    pub fn open(self, topology: &I2cTopology, roots: &FsRoots) -> Box<dyn Sampler> {
+        Box::new(SuspendSampler::new(
+            self.open_counts(topology, roots),
+            roots.clone(),
+        ))
+    }
+
+    /// Open a live sampler that also reads power state on every sample.
+    ///
+    /// Snapshots additionally carry the runtime PM state of the topology's
+    /// devices and the CPU idle-state and RAPL energy counters, for the TUI
+    /// and recordings.
+    pub fn open_with_power(self, topology: &I2cTopology, roots: &FsRoots) -> Box<dyn Sampler> {
+        let counts = self.open_counts(topology, roots);
+        let power = Box::new(PowerSampler::new(counts, power::device_dirs(topology)));
+        let idle = Box::new(IdleSampler::new(power, roots.clone()));
+        let energy = Box::new(RaplSampler::new(idle, roots.clone()));
+        Box::new(SuspendSampler::new(energy, roots.clone()))
+    }
+
+    fn open_counts(self, topology: &I2cTopology, roots: &FsRoots) -> Box<dyn Sampler> {
+        match self {
+            Backend::Procfs => Box::new(ProcSampler::new(roots.clone())),
+            Backend::Sysfs => {
+                let irqs = topology.all_sources().into_iter().map(|s| s.irq).collect();
+                Box::new(SysfsIrqSampler::new(roots.clone(), irqs))
+            }
+        }
+    }
+}
+
+/// Samples every IRQ from /proc/interrupts.
+pub struct ProcSampler {
+    start: Instant,
+    roots: FsRoots,
+}
+
+impl ProcSampler {
+    pub fn new(roots: FsRoots) -> Self {
+        Self {
+            start: Instant::now(),
+            roots,
+        }
+    }
+}
+
+impl Sampler for ProcSampler {
+    fn sample(&mut self) -> Result<Option<Snapshot>> {
+        let timestamp = self.start.elapsed().as_secs_f64();
+        let counts = interrupts::read_interrupts(&self.roots)?
+            .into_iter()
+            .map(|s| (s.irq, s.per_cpu))
+            .collect();
+        Ok(Some(Snapshot {
+            timestamp,
+            counts,
+            power: HashMap::new(),
+            idle: Vec::new(),
+            energy: Vec::new(),
+            suspend: None,
+        }))
+    }
+}
+
+/// Samples selected IRQs from /sys/kernel/irq/<n>/per_cpu_count.
+///
+/// Cheaper than parsing all of /proc/interrupts when only a few IRQs are of interest.
+pub struct SysfsIrqSampler {
+    start: Instant,
+    roots: FsRoots,
+    irqs: Vec<String>,
+}
+
+impl SysfsIrqSampler {
+    pub fn new(roots: FsRoots, irqs: Vec<String>) -> Self {
+        Self {
+            start: Instant::now(),
+            roots,
+            irqs,
+        }
+    }
+}
+
+impl Sampler for SysfsIrqSampler {
+    fn sample(&mut self) -> Result<Option<Snapshot>> {
+        let timestamp = self.start.elapsed().as_secs_f64();
+        let mut counts = HashMap::new();
+
+        for irq in &self.irqs {
+            let path = self
+                .roots
+                .sys_path("kernel/irq")
+                .join(irq)
+                .join("per_cpu_count");
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            counts.insert(irq.clone(), parse_per_cpu_count(&content));
+        }
+
+        Ok(Some(Snapshot {
+            timestamp,
+            counts,
+            power: HashMap::new(),
+            idle: Vec::new(),
+            energy: Vec::new(),
+            suspend: None,
+        }))
+    }
+}
+
+/// Parse a per_cpu_count file ("12,0,345,0").
+///
+/// The kernel prints one column per possible CPU, so the position is the CPU id.
+fn parse_per_cpu_count(content: &str) -> Vec<(u32, u64)> {
+    content
+        .trim()
+        .split(',')
+        .enumerate()
+        .filter_map(|(cpu, n)| Some((cpu as u32, n.trim().parse().ok()?)))
+        .collect()
+}
+
+/// Replays snapshots from a recording.
+pub struct ReplaySampler {
+    snapshots: std::vec::IntoIter<Snapshot>,
+}
+
+impl ReplaySampler {
+    pub fn new(snapshots: Vec<Snapshot>) -> Self {
+        Self {
+            snapshots: snapshots.into_iter(),
+        }
+    }
+}
+
+impl Sampler for ReplaySampler {
+    fn sample(&mut self) -> Result<Option<Snapshot>> {
+        Ok(self.snapshots.next())
+    }
+
+    fn is_live(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::{self, Fixture};
+
+    #[test]
+    fn test_parse_per_cpu_count() {
+        assert_eq!(
+            parse_per_cpu_count("12,0,345,0\n"),
+            vec![(0, 12), (1, 0), (2, 345), (3, 0)]
+        );
+        assert_eq!(parse_per_cpu_count("7\n"), vec![(0, 7)]);
+    }
+
+    #[test]
+    fn test_sysfs_irq_sampler() {
+        let fixture = Fixture::new();
+        fixture
+            .file("sys/kernel/irq/21/per_cpu_count", "4000,12\n")
+            .file("sys/kernel/irq/203/per_cpu_count", "0,137\n");
+
+        let irqs = vec!["21".to_string(), "203".to_string()];
+        let mut sampler = SysfsIrqSampler::new(fixture.roots(), irqs);
+        let snapshot = sampler.sample().unwrap().unwrap();
+
+        assert_eq!(snapshot.counts["21"], vec![(0, 4000), (1, 12)]);
+        assert_eq!(snapshot.total("203"), Some(137));
+    }
+
+    #[test]
+    fn test_replay_sampler() {
+        let snapshots = vec![
+            fixture::snapshot(0.0, &[("42", &[(0, 10), (1, 5)])]),
+            fixture::snapshot(1.0, &[("42", &[(0, 20), (1, 15)])]),
+        ];
+        let mut sampler = ReplaySampler::new(snapshots);
+
+        assert_eq!(sampler.sample().unwrap().unwrap().total("42"), Some(15));
+        assert_eq!(sampler.sample().unwrap().unwrap().total("42"), Some(35));
+        assert!(sampler.sample().unwrap().is_none());
+    }
+}
@@ -0,0 +1,95 @@
+//! Drift-free sampling schedule.
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Samples later than this fraction of the interval count as late.
+const LATE_FRACTION: u32 = 10;
+
+/// Sample deadlines on a fixed grid (`start + n * interval`).
+///
+/// Unlike sleeping for the interval after each sample, time spent drawing,
+/// handling keys or reading counters does not accumulate as drift. Grid slots
+/// that passed without a sample are skipped and counted as missed instead of
+/// being caught up in a burst.
+pub struct Schedule {
+    start: Instant,
+    interval: Duration,
+    /// Grid index of the next deadline
+    slot: u32,
+    /// Samples taken noticeably after their deadline
+    pub late: u32,
+    /// Grid slots skipped without a sample
+    pub missed: u32,
+}
+
+impl Schedule {
+    /// Start a schedule whose first deadline is one interval from now.
+    pub fn new(interval: Duration) -> Self {
+        Self::starting_at(Instant::now(), interval)
+    }
+
+    pub fn starting_at(start: Instant, interval: Duration) -> Self {
+        Self {
+            start,
+            interval,
+            slot: 1,
+            late: 0,
+            missed: 0,
+        }
+    }
+
+    /// When the next sample is due.
+    pub fn deadline(&self) -> Instant {
+        self.start + self.interval * self.slot
+    }
+
+    /// Time left until the next sample is due.
+    pub fn until_next(&self, now: Instant) -> Duration {
+        self.deadline().saturating_duration_since(now)
+    }
+
+    /// Sleep until the next sample is due.
+    pub fn wait(&self) {
+        thread::sleep(self.until_next(Instant::now()));
+    }
+
+    /// Record a sample taken at `now` and move on to the next free slot.
+    pub fn advance(&mut self, now: Instant) {
+        let lateness = now.saturating_duration_since(self.deadline());
+        if lateness > self.interval / LATE_FRACTION {
+            self.late += 1;
+        }
+
+        let skipped = if self.interval.is_zero() {
+            0
+        } else {
+            (lateness.as_nanos() / self.interval.as_nanos()) as u32
+        };
+        self.missed += skipped;
+        self.slot += 1 + skipped;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schedule_does_not_drift() {
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+        let mut schedule = Schedule::starting_at(start, ms(1000));
+
+        // A sample taken slightly late does not shift later deadlines
+        schedule.advance(start + ms(1050));
+        assert_eq!(schedule.deadline(), start + ms(2000));
+        assert_eq!((schedule.late, schedule.missed), (0, 0));
+
+        // A stall past two deadlines skips them instead of bursting
+        schedule.advance(start + ms(4300));
+        assert_eq!(schedule.deadline(), start + ms(5000));
+        assert_eq!((schedule.late, schedule.missed), (1, 2));
+        assert_eq!(schedule.until_next(start + ms(4600)), ms(400));
+    }
+}
@@ -0,0 +1,172 @@
+//! Summary statistics for interrupt rate series.
+
+/// Arithmetic mean, or `None` for an empty series.
+pub fn mean(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    Some(values.iter().sum::<f64>() / values.len() as f64)
+}
+
+/// Percentile (0-100) with linear interpolation between closest ranks.
+pub fn percentile(values: &[f64], pct: f64) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+
+    let rank = pct.clamp(0.0, 100.0) / 100.0 * (sorted.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    let weight = rank - lower as f64;
+    Some(sorted[lower] + (sorted[upper] - sorted[lower]) * weight)
+}
+
+/// Median of a series.
+pub fn median(values: &[f64]) -> Option<f64> {
+    percentile(values, 50.0)
+}
+
+/// Running Pearson correlation and least-squares slope of y against x.
+///
+/// Keeps only the means and summed (co)deviations, updated with Welford's
+/// method, so arbitrarily long series take constant memory.
+#[derive(Debug, Clone, Default)]
+pub struct LinearFit {
+    n: usize,
+    mean_x: f64,
+    mean_y: f64,
+    sum_dx2: f64,
+    sum_dy2: f64,
+    sum_dxdy: f64,
+}
+
+impl LinearFit {
+    pub fn push(&mut self, x: f64, y: f64) {
+        self.n += 1;
+        let n = self.n as f64;
+        let dx = x - self.mean_x;
+        let dy = y - self.mean_y;
+        self.mean_x += dx / n;
+        self.mean_y += dy / n;
+        self.sum_dx2 += dx * (x - self.mean_x);
+        self.sum_dy2 += dy * (y - self.mean_y);
+        self.sum_dxdy += dx * (y - self.mean_y);
+    }
+
+    /// (correlation, slope), `None` with fewer than three pairs or when
+    /// either series is constant.
+    pub fn fit(&self) -> Option<(f64, f64)> {
+        if self.n < 3 || self.sum_dx2 == 0.0 || self.sum_dy2 == 0.0 {
+            return None;
+        }
+        Some((
+            self.sum_dxdy / (self.sum_dx2 * self.sum_dy2).sqrt(),
+            self.sum_dxdy / self.sum_dx2,
+        ))
+    }
+}
+
+/// Small deterministic PRNG (xorshift64*), so reports are reproducible.
+struct XorShift(u64);
+
+impl XorShift {
+    fn next_index(&mut self, len: usize) -> usize {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        (self.0.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 33) as usize % len
+    }
+
+    fn resample<'a, T>(&mut self, items: &'a [T]) -> Vec<&'a T> {
+        (0..items.len())
+            .map(|_| &items[self.next_index(items.len())])
+            .collect()
+    }
+}
+
+/// Bootstrap resamples used for confidence intervals.
+const BOOTSTRAP_ROUNDS: usize = 2000;
+
+/// 95% bootstrap confidence interval of the relative change of a statistic.
+///
+/// Both runs are resampled independently; `stat` computes the statistic of
+/// one resampled run. Returns the 2.5th and 97.5th percentile of
+/// `stat(after) / stat(before) - 1`, or `None` if the statistic is undefined
+/// for too many resamples.
+pub fn relative_change_ci<T>(
+    before: &[T],
+    after: &[T],
+    stat: impl Fn(&[&T]) -> Option<f64>,
+) -> Option<(f64, f64)> {
+    if before.is_empty() || after.is_empty() {
+        return None;
+    }
+
+    let mut rng = XorShift(0x9E37_79B9_7F4A_7C15);
+    let mut changes = Vec::with_capacity(BOOTSTRAP_ROUNDS);
+
+    for _ in 0..BOOTSTRAP_ROUNDS {
+        let b = stat(&rng.resample(before));
+        let a = stat(&rng.resample(after));
+        if let (Some(b), Some(a)) = (b, a)
+            && b > 0.0
+        {
+            changes.push(a / b - 1.0);
+        }
+    }
+
+    // Require most resamples to be usable, otherwise the interval is meaningless
+    if changes.len() < BOOTSTRAP_ROUNDS / 2 {
+        return None;
+    }
+    Some((percentile(&changes, 2.5)?, percentile(&changes, 97.5)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentiles() {
+        let values = [5.0, 1.0, 4.0, 2.0, 3.0];
+        assert_eq!(mean(&values), Some(3.0));
+        assert_eq!(median(&values), Some(3.0));
+        assert_eq!(percentile(&values, 0.0), Some(1.0));
+        assert_eq!(percentile(&values, 100.0), Some(5.0));
+        assert_eq!(percentile(&values, 95.0), Some(4.8));
+        assert_eq!(median(&[]), None);
+    }
+
+    #[test]
+    fn test_linear_fit() {
+        let fit = |pairs: &[(f64, f64)]| {
+            let mut fit = LinearFit::default();
+            for &(x, y) in pairs {
+                fit.push(x, y);
+            }
+            fit.fit()
+        };
+        let pairs = [(0.0, 90.0), (1000.0, 80.0), (2000.0, 70.0), (3000.0, 60.0)];
+        let (r, slope) = fit(&pairs).unwrap();
+        assert!((r + 1.0).abs() < 1e-9);
+        assert!((slope + 0.01).abs() < 1e-12);
+        assert_eq!(fit(&pairs.map(|(x, _)| (x, 5.0))), None);
+        assert_eq!(fit(&pairs[..2]), None);
+    }
+
+    #[test]
+    fn test_relative_change_ci() {
+        let before: Vec<f64> = (0..50).map(|i| 100.0 + (i % 5) as f64).collect();
+        let after: Vec<f64> = (0..50).map(|i| 50.0 + (i % 5) as f64).collect();
+        let stat = |values: &[&f64]| mean(&values.iter().map(|v| **v).collect::<Vec<_>>());
+
+        let (low, high) = relative_change_ci(&before, &after, stat).unwrap();
+        assert!(low < high);
+        assert!(low > -0.52 && high < -0.48, "{} {}", low, high);
+
+        // A run without data has no interval
+        assert!(relative_change_ci(&before, &[], stat).is_none());
+    }
+}
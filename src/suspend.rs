@@ -0,0 +1,238 @@
+//! System suspend detection and S0ix residency.
+//!
+//! CLOCK_MONOTONIC stops while the system is suspended (including s2idle)
+//! and CLOCK_BOOTTIME does not, so their difference grows by the time spent
+//! suspended. A sampling interval in which it grew spans a suspend; its
+//! interrupt counts include the suspend and resume paths and do not describe
+//! a steady rate.
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::roots::FsRoots;
+use crate::sampler::{Sampler, Snapshot};
+
+/// Growth of BOOTTIME - MONOTONIC that counts as a suspend, in seconds.
+const MIN_SUSPEND_S: f64 = 0.1;
+
+/// Suspend-related state at one point in time.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SuspendState {
+    /// Time spent suspended since boot (CLOCK_BOOTTIME - CLOCK_MONOTONIC), in seconds
+    pub suspended_s: f64,
+    /// Successful suspends since boot (`suspend_stats/success`)
+    pub success: Option<u64>,
+    /// Failed suspend attempts since boot (`suspend_stats/fail`)
+    pub fail: Option<u64>,
+    /// Time the hardware spent in its deepest sleep state during the last suspend, in microseconds
+    pub last_hw_sleep_us: Option<u64>,
+    /// IRQ that woke the system from the last suspend (`/sys/power/pm_wakeup_irq`)
+    pub wakeup_irq: Option<String>,
+    /// Actions of the wakeup IRQ (e.g., "acpi", "PIXA3854:00")
+    pub wakeup_source: Option<String>,
+    /// Total S0ix residency (pmc_core `slp_s0_residency_usec`, or the ACPI LPIT counter)
+    pub s0ix_residency_us: Option<u64>,
+}
+
+/// A sampling interval that spans one or more suspends.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SuspendGap {
+    /// Time spent suspended, in seconds
+    pub duration_s: f64,
+    /// Successful suspends within the interval
+    pub suspends: u64,
+    /// Failed suspend attempts within the interval
+    pub failures: u64,
+    pub wakeup_irq: Option<String>,
+    pub wakeup_source: Option<String>,
+    /// S0ix residency gained within the interval, in seconds, if measurable
+    pub s0ix_s: Option<f64>,
+}
+
+impl SuspendGap {
+    /// Whether the platform reached S0ix, if known.
+    pub fn reached_s0ix(&self) -> Option<bool> {
+        self.s0ix_s.map(|s| s > 0.0)
+    }
+
+    /// One-line description (e.g., "suspended 35.2s, woken by IRQ 9 (acpi), S0ix reached (33.9s)").
+    pub fn describe(&self) -> String {
+        let mut text = format!("suspended {:.1}s", self.duration_s);
+        if self.failures > 0 {
+            text.push_str(&format!(", {} failed attempt(s)", self.failures));
+        }
+        match (&self.wakeup_irq, &self.wakeup_source) {
+            (Some(irq), Some(source)) => {
+                text.push_str(&format!(", woken by IRQ {} ({})", irq, source))
+            }
+            (Some(irq), None) => text.push_str(&format!(", woken by IRQ {}", irq)),
+            _ => {}
+        }
+        match self.s0ix_s {
+            Some(s) if s > 0.0 => text.push_str(&format!(", S0ix reached ({:.1}s)", s)),
+            Some(_) => text.push_str(", S0ix NOT reached"),
+            None => text.push_str(", S0ix unknown"),
+        }
+        text
+    }
+}
+
+/// Seconds on a clock.
+fn clock_s(clock: libc::clockid_t) -> f64 {
+    let mut ts = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    // SAFETY: `ts` is a valid, writable timespec for the duration of the call
+    unsafe { libc::clock_gettime(clock, &mut ts) };
+    ts.tv_sec as f64 + ts.tv_nsec as f64 / 1e9
+}
+
+/// Time the system has spent suspended since boot, in seconds.
+pub fn suspended_time() -> f64 {
+    (clock_s(libc::CLOCK_BOOTTIME) - clock_s(libc::CLOCK_MONOTONIC)).max(0.0)
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    let value = fs::read_to_string(path).ok()?;
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+fn read_u64(path: &Path) -> Option<u64> {
+    read_trimmed(path)?.parse().ok()
+}
+
+/// Read the suspend statistics, with `suspended_s` taken from the clocks.
+pub fn read_state(roots: &FsRoots, suspended_s: f64) -> SuspendState {
+    let stats = roots.sys_path("power/suspend_stats");
+    // pm_wakeup_irq is unreadable until a wakeup IRQ has been recorded
+    let wakeup_irq = read_trimmed(&roots.sys_path("power/pm_wakeup_irq"));
+    let wakeup_source = wakeup_irq
+        .as_ref()
+        .and_then(|irq| read_trimmed(&roots.sys_path(&format!("kernel/irq/{}/actions", irq))));
+    let s0ix_residency_us =
+        read_u64(&roots.sys_path("kernel/debug/pmc_core/slp_s0_residency_usec")).or_else(|| {
+            read_u64(
+                &roots.sys_path("devices/system/cpu/cpuidle/low_power_idle_system_residency_us"),
+            )
+        });
+
+    SuspendState {
+        suspended_s,
+        success: read_u64(&stats.join("success")),
+        fail: read_u64(&stats.join("fail")),
+        last_hw_sleep_us: read_u64(&stats.join("last_hw_sleep")),
+        wakeup_irq,
+        wakeup_source,
+        s0ix_residency_us,
+    }
+}
+
+/// The suspend between two states, if the system suspended in between.
+pub fn detect(before: &SuspendState, after: &SuspendState) -> Option<SuspendGap> {
+    let duration_s = (after.suspended_s - before.suspended_s).max(0.0);
+    let count = |b: Option<u64>, a: Option<u64>| match (b, a) {
+        (Some(b), Some(a)) => a.saturating_sub(b),
+        _ => 0,
+    };
+    let suspends = count(before.success, after.success);
+    let failures = count(before.fail, after.fail);
+    if duration_s < MIN_SUSPEND_S && suspends == 0 {
+        return None;
+    }
+
+    // The S0ix counter covers the whole interval; last_hw_sleep only the last suspend
+    let s0ix_us = match (before.s0ix_residency_us, after.s0ix_residency_us) {
+        (Some(b), Some(a)) => Some(a.saturating_sub(b)),
+        _ if suspends > 0 => after.last_hw_sleep_us,
+        _ => None,
+    };
+    Some(SuspendGap {
+        duration_s,
+        suspends,
+        failures,
+        wakeup_irq: after.wakeup_irq.clone(),
+        wakeup_source: after.wakeup_source.clone(),
+        s0ix_s: s0ix_us.map(|us| us as f64 / 1e6),
+    })
+}
+
+/// Adds suspend state to the snapshots of another sampler.
+pub struct SuspendSampler {
+    inner: Box<dyn Sampler>,
+    roots: FsRoots,
+}
+
+impl SuspendSampler {
+    pub fn new(inner: Box<dyn Sampler>, roots: FsRoots) -> Self {
+        Self { inner, roots }
+    }
+}
+
+impl Sampler for SuspendSampler {
+    fn sample(&mut self) -> Result<Option<Snapshot>> {
+        let Some(mut snapshot) = self.inner.sample()? else {
+            return Ok(None);
+        };
+        snapshot.suspend = Some(read_state(&self.roots, suspended_time()));
+        Ok(Some(snapshot))
+    }
+
+    fn is_live(&self) -> bool {
+        self.inner.is_live()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+
+    #[test]
+    fn test_detect_suspend() {
+        let fixture = Fixture::new();
+        fixture
+            .file("sys/power/suspend_stats/success", "3\n")
+            .file("sys/power/suspend_stats/fail", "0\n")
+            .file("sys/power/suspend_stats/last_hw_sleep", "0\n")
+            .file("sys/power/pm_wakeup_irq", "9\n")
+            .file("sys/kernel/irq/9/actions", "acpi\n")
+            .file(
+                "sys/kernel/debug/pmc_core/slp_s0_residency_usec",
+                "1000000\n",
+            );
+        let before = read_state(&fixture.roots(), 12.0);
+        assert_eq!(before.wakeup_source.as_deref(), Some("acpi"));
+
+        // No suspend while awake
+        assert_eq!(detect(&before, &before.clone()), None);
+
+        let after = SuspendState {
+            suspended_s: 47.2,
+            success: Some(4),
+            s0ix_residency_us: Some(34_900_000),
+            ..before.clone()
+        };
+        let gap = detect(&before, &after).unwrap();
+        assert_eq!(gap.suspends, 1);
+        assert!((gap.duration_s - 35.2).abs() < 1e-9);
+        assert_eq!(gap.reached_s0ix(), Some(true));
+        assert_eq!(
+            gap.describe(),
+            "suspended 35.2s, woken by IRQ 9 (acpi), S0ix reached (33.9s)"
+        );
+
+        // A suspend that never reached S0ix, known only from suspend_stats
+        let after = SuspendState {
+            suspended_s: 20.0,
+            success: Some(4),
+            s0ix_residency_us: None,
+            ..before.clone()
+        };
+        assert_eq!(detect(&before, &after).unwrap().reached_s0ix(), Some(false));
+    }
+}
@@ -0,0 +1,116 @@
+//! IRQ descriptors from /sys/kernel/irq.
+
+use std::fs;
+use std::path::Path;
+
+use crate::interrupts::InterruptSource;
+use crate::roots::FsRoots;
+
+/// An IRQ descriptor, as exposed in `/sys/kernel/irq/<n>/`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IrqDesc {
+    /// IRQ number
+    pub irq: String,
+    /// Interrupt chip name (`chip_name`, e.g., "amd_gpio")
+    pub chip: Option<String>,
+    /// Hardware IRQ number within the chip (`hwirq`)
+    pub hwirq: Option<String>,
+    /// Trigger type (`type`, e.g., "edge" or "level")
+    pub trigger: Option<String>,
+    /// Action (handler) names (`actions`), usually the device name
+    pub actions: Vec<String>,
+}
+
+impl IrqDesc {
+    /// Whether the IRQ has an action named exactly `name`.
+    pub fn has_action(&self, name: &str) -> bool {
+        self.actions.iter().any(|a| a == name)
+    }
+}
+
+impl From<&InterruptSource> for IrqDesc {
+    fn from(source: &InterruptSource) -> Self {
+        Self {
+            irq: source.irq.clone(),
+            chip: source.chip.clone(),
+            hwirq: source.hwirq.clone(),
+            trigger: source.trigger.clone(),
+            actions: source.actions.clone(),
+        }
+    }
+}
+
+fn read_attr(dir: &Path, name: &str) -> Option<String> {
+    let value = fs::read_to_string(dir.join(name)).ok()?;
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+/// Read every IRQ descriptor, sorted by IRQ number.
+///
+/// Returns an empty list if /sys/kernel/irq is unavailable (kernels without
+/// CONFIG_SPARSE_IRQ, or captured trees without it).
+pub fn read_irqs(roots: &FsRoots) -> Vec<IrqDesc> {
+    let Ok(entries) = fs::read_dir(roots.sys_path("kernel/irq")) else {
+        return Vec::new();
+    };
+
+    let mut irqs: Vec<(u32, IrqDesc)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let number: u32 = entry.file_name().to_str()?.parse().ok()?;
+            let dir = entry.path();
+            let actions = read_attr(&dir, "actions")
+                .map(|a| a.split(',').map(|a| a.trim().to_string()).collect())
+                .unwrap_or_default();
+            let desc = IrqDesc {
+                irq: number.to_string(),
+                chip: read_attr(&dir, "chip_name"),
+                hwirq: read_attr(&dir, "hwirq"),
+                trigger: read_attr(&dir, "type"),
+                actions,
+            };
+            Some((number, desc))
+        })
+        .collect();
+    irqs.sort_by_key(|(number, _)| *number);
+
+    irqs.into_iter().map(|(_, desc)| desc).collect()
+}
+
+/// Find the IRQ with an action named exactly `name`.
+pub fn find_irq_by_action(irqs: &[IrqDesc], name: &str) -> Option<String> {
+    irqs.iter()
+        .find(|desc| desc.has_action(name))
+        .map(|desc| desc.irq.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Fixture;
+
+    #[test]
+    fn test_read_irqs() {
+        let fixture = Fixture::new();
+        fixture
+            .file("sys/kernel/irq/21/actions", "idma64.5,i2c_designware.5\n")
+            .file("sys/kernel/irq/21/chip_name", "IR-IO-APIC\n")
+            .file("sys/kernel/irq/21/hwirq", "21\n")
+            .file("sys/kernel/irq/21/type", "level\n")
+            .file("sys/kernel/irq/9/actions", "acpi\n")
+            .file("sys/kernel/irq/3/actions", "\n");
+
+        let irqs = read_irqs(&fixture.roots());
+        assert_eq!(irqs[0].irq, "3");
+        assert!(irqs[0].actions.is_empty());
+        assert_eq!(irqs[1].irq, "9");
+        assert_eq!(irqs[2].chip.as_deref(), Some("IR-IO-APIC"));
+        assert_eq!(irqs[2].trigger.as_deref(), Some("level"));
+        assert_eq!(
+            find_irq_by_action(&irqs, "i2c_designware.5").as_deref(),
+            Some("21")
+        );
+        assert_eq!(find_irq_by_action(&irqs, "i2c_designware"), None);
+    }
+}
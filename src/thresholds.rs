@@ -0,0 +1,393 @@
+//! Per-source rate thresholds with warn and critical levels.
+//!
+//! A rule is written `KEY=WARN[,CRITICAL][,for=SECS][,hysteresis=PCT]`, on
+//! the command line or one per line in a limits file. The key is matched
+//! case-insensitively against a source's IRQ, ACPI name, controller name
+//! (controllers only) or device type, in that order of precedence; among
+//! equally specific rules the last one wins. Sources without a rule use the
+//! global `--threshold` as their warn level.
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::discovery::InterruptSourceInfo;
+
+/// Severity of a source's rate.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Level {
+    #[default]
+    Ok,
+    Warn,
+    Critical,
+}
+
+impl Level {
+    /// The next level down.
+    fn lower(self) -> Level {
+        match self {
+            Level::Critical => Level::Warn,
+            Level::Warn | Level::Ok => Level::Ok,
+        }
+    }
+
+    /// Name as in the machine-readable monitor formats.
+    pub fn name(self) -> &'static str {
+        match self {
+            Level::Ok => "ok",
+            Level::Warn => "warn",
+            Level::Critical => "critical",
+        }
+    }
+
+    /// Text for the `monitor` status column.
+    pub fn status(self) -> &'static str {
+        match self {
+            Level::Ok => "ok",
+            Level::Warn => "* WARN",
+            Level::Critical => "** CRIT",
+        }
+    }
+}
+
+/// Rate limits of one source.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limit {
+    /// Rate above which the source is at warn level, in irqs/s
+    pub warn: f64,
+    /// Rate above which the source is critical, in irqs/s
+    pub critical: Option<f64>,
+    /// Time a rate must stay above a level before the level is raised, in seconds
+    pub min_duration_s: f64,
+    /// Relative drop below a level's rate needed to leave the level, in percent
+    pub hysteresis: f64,
+}
+
+impl Limit {
+    fn plain(warn: f64) -> Self {
+        Self {
+            warn,
+            critical: None,
+            min_duration_s: 0.0,
+            hysteresis: 0.0,
+        }
+    }
+
+    /// Level of a rate, ignoring duration and hysteresis.
+    fn level(&self, rate: f64) -> Level {
+        match self.critical {
+            Some(critical) if rate > critical => Level::Critical,
+            _ if rate > self.warn && self.warn > 0.0 => Level::Warn,
+            _ => Level::Ok,
+        }
+    }
+
+    /// Rate a source at `level` must fall below to leave it.
+    fn release(&self, level: Level) -> f64 {
+        let rate = match level {
+            Level::Ok => return f64::INFINITY,
+            // A disabled warn level is never held
+            Level::Warn if self.warn <= 0.0 => return f64::INFINITY,
+            Level::Warn => self.warn,
+            Level::Critical => self.critical.unwrap_or(self.warn),
+        };
+        rate * (1.0 - self.hysteresis / 100.0)
+    }
+}
+
+impl fmt::Display for Limit {
+    /// Rule syntax without the key (e.g., "200,400,for=3,hysteresis=10").
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.warn)?;
+        if let Some(critical) = self.critical {
+            write!(f, ",{}", critical)?;
+        }
+        if self.min_duration_s > 0.0 {
+            write!(f, ",for={}", self.min_duration_s)?;
+        }
+        if self.hysteresis > 0.0 {
+            write!(f, ",hysteresis={}", self.hysteresis)?;
+        }
+        Ok(())
+    }
+}
+
+/// A limit for the sources matching a key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    pub key: String,
+    pub limit: Limit,
+}
+
+impl FromStr for Rule {
+    type Err = anyhow::Error;
+
+    fn from_str(spec: &str) -> Result<Self> {
+        let (key, values) = spec
+            .split_once('=')
+            .with_context(|| format!("expected KEY=WARN[,CRITICAL][,...], got {:?}", spec))?;
+        let key = key.trim();
+        if key.is_empty() {
+            anyhow::bail!("missing source in {:?}", spec);
+        }
+
+        let number = |value: &str| -> Result<f64> {
+            let value: f64 = value
+                .trim()
+                .parse()
+                .with_context(|| format!("invalid number {:?} in {:?}", value, spec))?;
+            if !(value >= 0.0 && value.is_finite()) {
+                anyhow::bail!("negative or non-finite number in {:?}", spec);
+            }
+            Ok(value)
+        };
+
+        let mut parts = values.split(',');
+        let mut limit = Limit::plain(number(parts.next().unwrap_or_default())?);
+        for part in parts {
+            match part.split_once('=') {
+                Some(("for", value)) => limit.min_duration_s = number(value)?,
+                Some(("hysteresis", value)) => {
+                    limit.hysteresis = number(value.trim_end_matches('%'))?;
+                    if limit.hysteresis >= 100.0 {
+                        anyhow::bail!("hysteresis must be below 100% in {:?}", spec);
+                    }
+                }
+                Some((option, _)) => anyhow::bail!("unknown option {:?} in {:?}", option, spec),
+                None if limit.critical.is_none() => limit.critical = Some(number(part)?),
+                None => anyhow::bail!("more than two levels in {:?}", spec),
+            }
+        }
+        if limit.critical.is_some_and(|critical| critical < limit.warn) {
+            anyhow::bail!("critical level below warn level in {:?}", spec);
+        }
+
+        Ok(Self {
+            key: key.to_string(),
+            limit,
+        })
+    }
+}
+
+/// How specifically a key names a source, `None` if it does not match.
+///
+/// Higher is more specific: IRQ, ACPI name, controller name (controllers
+/// only), device type (`controller` for all controllers).
+pub fn key_specificity(key: &str, source: &InterruptSourceInfo) -> Option<u8> {
+    if key == source.irq {
+        Some(4)
+    } else if source
+        .acpi_name
+        .as_deref()
+        .is_some_and(|name| name.eq_ignore_ascii_case(key))
+    {
+        Some(3)
+    } else if source.is_controller && source.controller.eq_ignore_ascii_case(key) {
+        Some(2)
+    } else if source.device_type.eq_ignore_ascii_case(key)
+        || (source.is_controller && key.eq_ignore_ascii_case("controller"))
+    {
+        Some(1)
+    } else {
+        None
+    }
+}
+
+/// Read rules from a limits file: one rule per line, `#` starts a comment.
+pub fn load_rules(path: &Path) -> Result<Vec<Rule>> {
+    let text =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let mut rules = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        // Allow "KEY = ..." with spaces around the values
+        let spec: String = line.split_whitespace().collect();
+        rules.push(
+            spec.parse()
+                .with_context(|| format!("{}:{}", path.display(), number + 1))?,
+        );
+    }
+    Ok(rules)
+}
+
+/// The limits of all sources.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Thresholds {
+    /// Warn level of sources without a rule
+    pub default_warn: f64,
+    pub rules: Vec<Rule>,
+}
+
+impl Thresholds {
+    pub fn new(default_warn: f64) -> Self {
+        Self {
+            default_warn,
+            rules: Vec::new(),
+        }
+    }
+
+    /// The limit of a source: its most specific rule, or the default.
+    pub fn limit_for(&self, source: &InterruptSourceInfo) -> Limit {
+        let mut best: Option<(u8, &Rule)> = None;
+        for rule in &self.rules {
+            if let Some(specificity) = key_specificity(&rule.key, source)
+                && best.is_none_or(|(s, _)| specificity >= s)
+            {
+                best = Some((specificity, rule));
+            }
+        }
+        best.map_or_else(|| Limit::plain(self.default_warn), |(_, rule)| rule.limit)
+    }
+}
+
+/// Level of one source over time.
+#[derive(Debug, Clone)]
+pub struct LevelTracker {
+    pub limit: Limit,
+    pub level: Level,
+    /// Highest level reached
+    pub peak: Level,
+    /// Time spent at warn level or above, and at critical level, in seconds
+    pub warn_s: f64,
+    pub critical_s: f64,
+    /// Time the rate has been above the current level, in seconds
+    rising_s: f64,
+}
+
+impl LevelTracker {
+    pub fn new(limit: Limit) -> Self {
+        Self {
+            limit,
+            level: Level::Ok,
+            peak: Level::Ok,
+            warn_s: 0.0,
+            critical_s: 0.0,
+            rising_s: 0.0,
+        }
+    }
+
+    /// Feed the rate of one interval of `dt_s` seconds and return the new level.
+    ///
+    /// A level is raised once the rate has exceeded it for the limit's
+    /// minimum duration, and left once the rate falls the hysteresis below it.
+    pub fn update(&mut self, rate: f64, dt_s: f64) -> Level {
+        let target = self.limit.level(rate);
+        if target > self.level {
+            self.rising_s += dt_s;
+            if self.rising_s >= self.limit.min_duration_s {
+                self.level = target;
+                self.rising_s = 0.0;
+            }
+        } else {
+            self.rising_s = 0.0;
+            // Step down while the rate is below the release point of the
+            // current level, so leaving critical still honours the warn band
+            while self.level > target && rate < self.limit.release(self.level) {
+                self.level = self.level.lower();
+            }
+        }
+
+        self.peak = self.peak.max(self.level);
+        if self.level >= Level::Warn {
+            self.warn_s += dt_s;
+        }
+        if self.level == Level::Critical {
+            self.critical_s += dt_s;
+        }
+        self.level
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture;
+
+    #[test]
+    fn test_parse_rule() {
+        let rule: Rule = "touchpad=200,400,for=3,hysteresis=10%".parse().unwrap();
+        assert_eq!(rule.key, "touchpad");
+        assert_eq!(rule.limit.warn, 200.0);
+        assert_eq!(rule.limit.critical, Some(400.0));
+        assert_eq!(rule.limit.min_duration_s, 3.0);
+        assert_eq!(rule.limit.hysteresis, 10.0);
+        assert_eq!(rule.limit.to_string(), "200,400,for=3,hysteresis=10");
+
+        let rule: Rule = "PIXA3854:00=150".parse().unwrap();
+        assert_eq!(rule.key, "PIXA3854:00");
+        assert_eq!(rule.limit.critical, None);
+
+        assert!("touchpad".parse::<Rule>().is_err());
+        assert!("touchpad=400,200".parse::<Rule>().is_err());
+        assert!("touchpad=200,300,400".parse::<Rule>().is_err());
+        assert!("touchpad=200,after=3".parse::<Rule>().is_err());
+    }
+
+    #[test]
+    fn test_most_specific_rule_wins() {
+        let sources = fixture::touchpad_sources();
+        let (controller, touchpad) = (&sources[0], &sources[1]);
+        let mut thresholds = Thresholds::new(100.0);
+        assert_eq!(thresholds.limit_for(touchpad).warn, 100.0);
+
+        thresholds.rules = ["controller=5000", "touchpad=200", "203=300", "TOUCHPAD=250"]
+            .iter()
+            .map(|spec| spec.parse().unwrap())
+            .collect();
+        assert_eq!(thresholds.limit_for(controller).warn, 5000.0);
+        assert_eq!(thresholds.limit_for(touchpad).warn, 300.0);
+
+        thresholds.rules.truncate(2);
+        thresholds.rules.push("touchpad=250".parse().unwrap());
+        assert_eq!(thresholds.limit_for(touchpad).warn, 250.0);
+
+        // A controller name does not select the HID devices on it
+        thresholds.rules = vec!["i2c_designware.5=4000".parse().unwrap()];
+        assert_eq!(thresholds.limit_for(controller).warn, 4000.0);
+        assert_eq!(thresholds.limit_for(touchpad).warn, 100.0);
+    }
+
+    #[test]
+    fn test_level_duration_and_hysteresis() {
+        let limit = "x=200,400,for=2,hysteresis=10"
+            .parse::<Rule>()
+            .unwrap()
+            .limit;
+        let mut tracker = LevelTracker::new(limit);
+
+        // Above critical for one interval is not enough
+        assert_eq!(tracker.update(500.0, 1.0), Level::Ok);
+        assert_eq!(tracker.update(150.0, 1.0), Level::Ok);
+        assert_eq!(tracker.update(500.0, 1.0), Level::Ok);
+        assert_eq!(tracker.update(500.0, 1.0), Level::Critical);
+
+        // Just below critical stays critical; below the hysteresis band drops to warn
+        assert_eq!(tracker.update(380.0, 1.0), Level::Critical);
+        assert_eq!(tracker.update(350.0, 1.0), Level::Warn);
+        assert_eq!(tracker.update(190.0, 1.0), Level::Warn);
+        assert_eq!(tracker.update(170.0, 1.0), Level::Ok);
+
+        assert_eq!(tracker.peak, Level::Critical);
+        assert_eq!(tracker.critical_s, 2.0);
+        assert_eq!(tracker.warn_s, 4.0);
+
+        // Leaving critical within the warn hysteresis band stops at warn
+        assert_eq!(tracker.update(500.0, 1.0), Level::Ok);
+        assert_eq!(tracker.update(500.0, 1.0), Level::Critical);
+        assert_eq!(tracker.update(190.0, 1.0), Level::Warn);
+        assert_eq!(tracker.update(170.0, 1.0), Level::Ok);
+
+        // Leaving critical below the warn band drops to ok at once
+        assert_eq!(tracker.update(500.0, 1.0), Level::Ok);
+        assert_eq!(tracker.update(500.0, 1.0), Level::Critical);
+        assert_eq!(tracker.update(100.0, 1.0), Level::Ok);
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeMap, VecDeque};
 use std::io::{self, Stdout};
 use std::time::{Duration, Instant};
 
@@ -10,8 +10,19 @@ use ratatui::prelude::*;
 use ratatui::symbols::Marker;
 use ratatui::widgets::{Axis, Block, Borders, Chart, Dataset, Paragraph, Row, Table};
 
-use crate::discovery::{self, I2cTopology, InterruptSourceInfo};
-use crate::interrupts;
+use crate::cpuidle::{self, IdleResidency, IdleState};
+use crate::detector::{self, Detector, Phase};
+use crate::discovery::{I2cTopology, InterruptSourceInfo};
+use crate::irq_model::{self, FifoConfig};
+use crate::power::{self, PowerState};
+use crate::rapl::{self, EnergyZone, ZonePower};
+use crate::recording::Recording;
+use crate::roots::FsRoots;
+use crate::sampler::{Backend, SampleSource, Snapshot};
+use crate::schedule::Schedule;
+use crate::stats::{self, LinearFit};
+use crate::suspend::{self, SuspendGap, SuspendState};
+use crate::thresholds::{Level, LevelTracker, Thresholds};
 
 /// Colors for individual interrupt sources - controllers get one set, HID devices get brighter variants.
 const CONTROLLER_COLORS: [Color; 4] = [Color::Blue, Color::Magenta, Color::Red, Color::Yellow];
@@ -26,12 +37,51 @@ const HID_COLORS: [Color; 4] = [
 /// Color for the TOTAL line.
 const TOTAL_COLOR: Color = Color::White;
 
+/// Colors for the per-CPU lines of the selected source.
+const CPU_COLORS: [Color; 8] = [
+    Color::Green,
+    Color::Cyan,
+    Color::Yellow,
+    Color::Magenta,
+    Color::LightGreen,
+    Color::LightBlue,
+    Color::LightRed,
+    Color::Gray,
+];
+
+/// Colors for the RAPL zone lines of the power chart.
+const POWER_COLORS: [Color; 4] = [
+    Color::LightGreen,
+    Color::Green,
+    Color::LightBlue,
+    Color::Gray,
+];
+
+/// What the main chart plots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChartMode {
+    /// Interrupt rate of every source
+    Rates,
+    /// Controller IRQs per HID IRQ for every controller
+    Ratio,
+}
+
 /// Maximum data points per source (scrolling window).
 const MAX_POINTS: usize = 300;
 
 /// Target Y-axis labels.
 const TARGET_Y_LABELS: f64 = 5.0;
 
+/// Event log lines shown in the TUI; older events are in the summary.
+const MAX_EVENT_LINES: usize = 5;
+
+/// Seconds skipped by one seek step during replay.
+const SEEK_STEP_S: f64 = 10.0;
+
+/// Replay speed limits.
+const MIN_SPEED: f64 = 0.125;
+const MAX_SPEED: f64 = 64.0;
+
 fn nice_step(max_value: f64) -> f64 {
     if max_value <= 0.0 {
         return 1.0;
@@ -55,6 +105,13 @@ fn ceil_to_step(value: f64, step: f64) -> f64 {
     (value / step).ceil() * step
 }
 
+/// Round the largest plotted rate up to a nice axis maximum.
+fn axis_max(max_rate: f64) -> f64 {
+    let raw_max = (max_rate * 1.1).max(10.0);
+    let step = nice_step(raw_max);
+    ceil_to_step(raw_max, step)
+}
+
 /// History for a single interrupt source.
 struct SourceHistory {
     /// IRQ number
@@ -63,16 +120,42 @@ struct SourceHistory {
     name: String,
     /// Device type (e.g., "Touchpad")
     device_type: String,
+    /// Interrupt chip name (e.g., "intel-gpio")
+    chip: Option<String>,
+    /// Trigger or flow type (e.g., "edge")
+    trigger: Option<String>,
+    /// Controller name (the source itself for controllers)
+    controller: String,
+    /// Sysfs device name the power state is keyed by
+    device: String,
     /// Whether this is a controller
     is_controller: bool,
+    /// Size of the largest input report in bytes (HID devices only)
+    max_input_report: Option<usize>,
     /// Assigned color index (stable across visibility changes)
     color_idx: usize,
     /// Time series: (elapsed_s, rate_per_s)
     data: VecDeque<(f64, f64)>,
     /// Previous sample's count
     prev_count: u64,
+    /// Previous sample's counts keyed by CPU id
+    prev_per_cpu: BTreeMap<u32, u64>,
+    /// Per-CPU time series: (elapsed_s, rate_per_s), keyed by CPU id
+    cpu_data: BTreeMap<u32, VecDeque<(f64, f64)>>,
     /// Latest rate
     latest_rate: f64,
+    /// Interrupts counted since the first snapshot
+    total_delta: u64,
+    /// Controller-to-HID ratio series (controllers only): (elapsed_s, ratio)
+    ratio_data: VecDeque<(f64, f64)>,
+    /// Latest controller-to-HID ratio, `None` while the HID devices are idle
+    latest_ratio: Option<f64>,
+    /// Runtime PM state at the start of the sampling window
+    first_power: Option<PowerState>,
+    /// Latest runtime PM state
+    latest_power: Option<PowerState>,
+    /// Warn/critical level against the source's limit
+    level: LevelTracker,
     /// Running statistics
     rate_sum: f64,
     rate_min: f64,
@@ -82,16 +165,37 @@ struct SourceHistory {
 }
 
 impl SourceHistory {
-    fn new(info: &InterruptSourceInfo, initial_count: u64, color_idx: usize) -> Self {
+    fn new(
+        info: &InterruptSourceInfo,
+        initial_per_cpu: &[(u32, u64)],
+        color_idx: usize,
+        level: LevelTracker,
+    ) -> Self {
         Self {
             irq: info.irq.clone(),
             name: info.name.clone(),
             device_type: info.device_type.clone(),
+            chip: info.chip.clone(),
+            trigger: info.trigger.clone(),
+            controller: info.controller.clone(),
+            device: info.device.clone(),
             is_controller: info.is_controller,
+            max_input_report: info.max_input_report,
             color_idx,
             data: VecDeque::with_capacity(MAX_POINTS),
-            prev_count: initial_count,
+            prev_count: initial_per_cpu.iter().map(|&(_, count)| count).sum(),
+            prev_per_cpu: initial_per_cpu.iter().copied().collect(),
+            cpu_data: initial_per_cpu
+                .iter()
+                .map(|&(cpu, _)| (cpu, VecDeque::with_capacity(MAX_POINTS)))
+                .collect(),
             latest_rate: 0.0,
+            total_delta: 0,
+            ratio_data: VecDeque::with_capacity(MAX_POINTS),
+            latest_ratio: None,
+            first_power: None,
+            latest_power: None,
+            level,
             rate_sum: 0.0,
             rate_min: f64::MAX,
             rate_max: f64::MIN,
@@ -99,20 +203,77 @@ impl SourceHistory {
         }
     }
 
-    fn push(&mut self, elapsed_s: f64, count: u64, interval_s: f64) {
+    /// Add a sample taken `dt_s` seconds after the previous one.
+    fn push(&mut self, elapsed_s: f64, per_cpu: &[(u32, u64)], dt_s: f64) {
+        let count: u64 = per_cpu.iter().map(|&(_, count)| count).sum();
         let delta = count.saturating_sub(self.prev_count);
-        let rate = delta as f64 / interval_s;
+        let rate = delta as f64 / dt_s;
 
         if self.data.len() >= MAX_POINTS {
             self.data.pop_front();
         }
         self.data.push_back((elapsed_s, rate));
 
+        // CPUs may come online between samples, so match counts by CPU id
+        for &(cpu, cpu_count) in per_cpu {
+            let prev = self.prev_per_cpu.get(&cpu).copied().unwrap_or(cpu_count);
+            let cpu_rate = cpu_count.saturating_sub(prev) as f64 / dt_s;
+            let history = self
+                .cpu_data
+                .entry(cpu)
+                .or_insert_with(|| VecDeque::with_capacity(MAX_POINTS));
+            if history.len() >= MAX_POINTS {
+                history.pop_front();
+            }
+            history.push_back((elapsed_s, cpu_rate));
+        }
+
         self.prev_count = count;
+        self.prev_per_cpu = per_cpu.iter().copied().collect();
         self.latest_rate = rate;
+        self.total_delta += delta;
         self.rate_sum += rate;
         self.rate_min = self.rate_min.min(rate);
         self.rate_max = self.rate_max.max(rate);
+        self.level.update(rate, dt_s);
+    }
+
+    /// Take new counts as the baseline without adding a sample.
+    fn skip(&mut self, per_cpu: &[(u32, u64)]) {
+        self.prev_count = per_cpu.iter().map(|&(_, count)| count).sum();
+        self.prev_per_cpu = per_cpu.iter().copied().collect();
+    }
+
+    fn push_ratio(&mut self, elapsed_s: f64, ratio: Option<f64>) {
+        self.latest_ratio = ratio;
+        if let Some(ratio) = ratio {
+            if self.ratio_data.len() >= MAX_POINTS {
+                self.ratio_data.pop_front();
+            }
+            self.ratio_data.push_back((elapsed_s, ratio));
+        }
+    }
+
+    fn push_power(&mut self, power: Option<&PowerState>) {
+        let Some(power) = power else {
+            return;
+        };
+        if self.first_power.is_none() {
+            self.first_power = Some(power.clone());
+        }
+        self.latest_power = Some(power.clone());
+    }
+
+    /// Share of the sampling window the device spent runtime-active, in percent.
+    fn active_percent(&self) -> Option<f64> {
+        power::active_percent(self.first_power.as_ref()?, self.latest_power.as_ref()?)
+    }
+
+    /// Wakeup events signaled during the sampling window.
+    fn wakeups(&self) -> Option<u64> {
+        let first = self.first_power.as_ref()?.wakeup_count?;
+        let latest = self.latest_power.as_ref()?.wakeup_count?;
+        Some(latest.saturating_sub(first))
     }
 
     fn color(&self) -> Color {
@@ -133,6 +294,27 @@ impl SourceHistory {
     }
 }
 
+/// Power time series of one RAPL zone.
+struct PowerHistory {
+    /// Zone directory (e.g., "intel-rapl:0:0")
+    zone: String,
+    /// Zone name (e.g., "core")
+    name: String,
+    /// Time series: (elapsed_s, watts)
+    data: VecDeque<(f64, f64)>,
+}
+
+/// Average power during low- and high-interrupt intervals.
+struct EnergySplit {
+    /// Mean total interrupt rate of the low and high intervals
+    low_rate: f64,
+    high_rate: f64,
+    low_intervals: usize,
+    high_intervals: usize,
+    /// (zone name, low watts, high watts)
+    zones: Vec<(String, f64, f64)>,
+}
+
 /// Application state.
 pub struct App {
     sources: Vec<SourceHistory>,
@@ -142,12 +324,54 @@ pub struct App {
     total_min: f64,
     total_max: f64,
     sample_count: u32,
-    start: Instant,
+    /// Timestamp of the latest snapshot, relative to the first one
+    elapsed: f64,
+    /// Timestamp of the initial snapshot
+    start_timestamp: f64,
+    /// Timestamp of the latest snapshot, for the measured interval
+    prev_timestamp: f64,
     interval_ms: u64,
+    /// Live samples taken noticeably after their deadline
+    late_samples: u32,
+    /// Live sampling slots skipped entirely
+    missed_samples: u32,
     pub should_quit: bool,
     selected_idx: usize,
     total_visible: bool,
-    threshold: f64,
+    show_cpu_panel: bool,
+    chart_mode: ChartMode,
+    /// Default and per-source rate limits
+    thresholds: Thresholds,
+    /// RX FIFO model for the expected controller/HID ratio
+    fifo: FifoConfig,
+    /// Idle-state counters of the first snapshot that had them, and its timestamp
+    first_idle: Option<(f64, Vec<IdleState>)>,
+    /// Idle-state counters of the latest snapshot, and its timestamp
+    prev_idle: Option<(f64, Vec<IdleState>)>,
+    /// Idle-state residency over the latest interval
+    latest_idle: Vec<IdleResidency>,
+    /// Deepest-state residency against the total interrupt rate, per interval
+    idle_fit: LinearFit,
+    show_idle_panel: bool,
+    /// RAPL counters of the latest snapshot that had them, and its timestamp
+    prev_energy: Option<(f64, Vec<EnergyZone>)>,
+    /// Power time series per RAPL zone
+    power_data: Vec<PowerHistory>,
+    /// Per-interval total interrupt rate and zone power, for the summary
+    energy_intervals: Vec<(f64, Vec<ZonePower>)>,
+    show_power_panel: bool,
+    /// Suspend state of the latest snapshot
+    prev_suspend: Option<SuspendState>,
+    /// Intervals that spanned a system suspend, by elapsed time at resume
+    suspend_gaps: Vec<(f64, SuspendGap)>,
+    /// Seconds a storm or stuck condition must hold before it is logged
+    min_duration_s: f64,
+    detector: Option<Detector>,
+    /// Storm and stuck-source events, oldest first
+    events: Vec<detector::Event>,
+    show_event_panel: bool,
+    /// Playback state shown in the status bar when replaying a recording
+    replay: Option<ReplayStatus>,
 }
 
 impl App {
@@ -160,29 +384,81 @@ impl App {
             total_min: f64::MAX,
             total_max: f64::MIN,
             sample_count: 0,
-            start: Instant::now(),
+            elapsed: 0.0,
+            start_timestamp: 0.0,
+            prev_timestamp: 0.0,
             interval_ms,
+            late_samples: 0,
+            missed_samples: 0,
             should_quit: false,
             selected_idx: 0,
             total_visible: false,
-            threshold,
+            show_cpu_panel: true,
+            chart_mode: ChartMode::Rates,
+            thresholds: Thresholds::new(threshold),
+            fifo: FifoConfig::default(),
+            first_idle: None,
+            prev_idle: None,
+            latest_idle: Vec::new(),
+            idle_fit: LinearFit::default(),
+            show_idle_panel: true,
+            prev_energy: None,
+            power_data: Vec::new(),
+            energy_intervals: Vec::new(),
+            show_power_panel: false,
+            prev_suspend: None,
+            suspend_gaps: Vec::new(),
+            min_duration_s: detector::DEFAULT_MIN_DURATION_S,
+            detector: None,
+            events: Vec::new(),
+            show_event_panel: true,
+            replay: None,
         }
     }
 
+    /// Drop all history and start over from `initial`, keeping the view settings.
+    fn reset(&mut self, topology: &I2cTopology, initial: &Snapshot) {
+        let visible: Vec<bool> = self.sources.iter().map(|s| s.visible).collect();
+
+        let mut fresh = App::new(self.interval_ms, self.thresholds.default_warn);
+        fresh.thresholds = self.thresholds.clone();
+        fresh.min_duration_s = self.min_duration_s;
+        fresh.init_from_topology(topology, initial);
+        for (source, visible) in fresh.sources.iter_mut().zip(visible) {
+            source.visible = visible;
+        }
+        fresh.selected_idx = self.selected_idx;
+        fresh.total_visible = self.total_visible;
+        fresh.show_cpu_panel = self.show_cpu_panel;
+        fresh.show_idle_panel = self.show_idle_panel;
+        fresh.show_power_panel = self.show_power_panel;
+        fresh.show_event_panel = self.show_event_panel;
+        fresh.chart_mode = self.chart_mode;
+        fresh.fifo = self.fifo;
+        fresh.replay = self.replay;
+
+        *self = fresh;
+    }
+
     /// Initialize from discovered topology.
-    pub fn init_from_topology(
-        &mut self,
-        topology: &I2cTopology,
-        initial_counts: &HashMap<String, u64>,
-    ) {
+    pub fn init_from_topology(&mut self, topology: &I2cTopology, initial: &Snapshot) {
         self.sources.clear();
+        self.start_timestamp = initial.timestamp;
+        self.prev_timestamp = initial.timestamp;
+        self.push_idle(initial.timestamp, &initial.idle, 0.0);
+        self.push_energy(initial.timestamp, &initial.energy, 0.0);
+        self.prev_suspend = initial.suspend.clone();
 
         let sources = topology.all_sources();
         let mut controller_idx = 0usize;
         let mut hid_idx = 0usize;
 
         for info in &sources {
-            let count = initial_counts.get(&info.irq).copied().unwrap_or(0);
+            let per_cpu = initial
+                .counts
+                .get(&info.irq)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
             let color_idx = if info.is_controller {
                 let idx = controller_idx;
                 controller_idx += 1;
@@ -192,9 +468,16 @@ impl App {
                 hid_idx += 1;
                 idx
             };
-            self.sources
-                .push(SourceHistory::new(info, count, color_idx));
+            let level = LevelTracker::new(self.thresholds.limit_for(info));
+            let mut source = SourceHistory::new(info, per_cpu, color_idx, level);
+            source.push_power(initial.power.get(&info.device));
+            self.sources.push(source);
         }
+        self.detector = Some(Detector::new(
+            &sources,
+            &self.thresholds,
+            self.min_duration_s,
+        ));
     }
 
     fn selectable_count(&self) -> usize {
@@ -229,8 +512,15 @@ impl App {
         }
     }
 
+    fn toggle_chart_mode(&mut self) {
+        self.chart_mode = match self.chart_mode {
+            ChartMode::Rates => ChartMode::Ratio,
+            ChartMode::Ratio => ChartMode::Rates,
+        };
+    }
+
     fn elapsed_s(&self) -> f64 {
-        self.start.elapsed().as_secs_f64()
+        self.elapsed
     }
 
     fn y_max(&self) -> f64 {
@@ -239,29 +529,31 @@ impl App {
             if !source.visible {
                 continue;
             }
-            for &(_, rate) in &source.data {
-                max = max.max(rate);
+            let data = match self.chart_mode {
+                ChartMode::Rates => &source.data,
+                ChartMode::Ratio => &source.ratio_data,
+            };
+            for &(_, value) in data {
+                max = max.max(value);
             }
         }
-        if self.total_visible {
+        if self.total_visible && self.chart_mode == ChartMode::Rates {
             for &(_, rate) in &self.total_history {
                 max = max.max(rate);
             }
         }
-        let raw_max = (max * 1.1).max(10.0);
-        let step = nice_step(raw_max);
-        ceil_to_step(raw_max, step)
+        axis_max(max)
     }
 
-    fn y_labels(&self, y_max: f64) -> Vec<Span<'static>> {
+    fn y_labels(&self, y_max: f64, unit: &str) -> Vec<Span<'static>> {
         let step = nice_step(y_max);
         let mut labels = Vec::new();
         let mut y = 0.0;
         while y <= y_max + step * 0.01 {
             if y == y.floor() {
-                labels.push(Span::raw(format!("{:.0}/s", y)));
+                labels.push(Span::raw(format!("{:.0}{}", y, unit)));
             } else {
-                labels.push(Span::raw(format!("{:.1}/s", y)));
+                labels.push(Span::raw(format!("{:.1}{}", y, unit)));
             }
             y += step;
         }
@@ -277,20 +569,245 @@ impl App {
         }
     }
 
+    /// HID devices attached to a controller.
+    fn hid_sources<'a>(&'a self, controller: &'a str) -> impl Iterator<Item = &'a SourceHistory> {
+        self.sources
+            .iter()
+            .filter(move |s| !s.is_controller && s.controller == controller)
+    }
+
+    /// Latest summed rate of the HID devices attached to a controller.
+    fn hid_rate(&self, controller: &str) -> f64 {
+        self.hid_sources(controller).map(|s| s.latest_rate).sum()
+    }
+
+    /// Controller IRQs per HID IRQ over the whole session.
+    fn session_ratio(&self, source: &SourceHistory) -> Option<f64> {
+        if !source.is_controller {
+            return None;
+        }
+        let hid_total: u64 = self
+            .hid_sources(&source.controller)
+            .map(|s| s.total_delta)
+            .sum();
+        (hid_total > 0).then(|| source.total_delta as f64 / hid_total as f64)
+    }
+
+    /// Controller IRQs per HID IRQ predicted from report sizes and the RX FIFO.
+    ///
+    /// Only DesignWare controllers are modelled; `None` if no attached device
+    /// has a known report size.
+    fn expected_ratio(&self, source: &SourceHistory) -> Option<f64> {
+        if !source.is_controller || !irq_model::applies_to(&source.controller) {
+            return None;
+        }
+        let devices: Vec<_> = self
+            .hid_sources(&source.controller)
+            .map(|s| (s.max_input_report, s.total_delta))
+            .collect();
+        self.fifo.expected_ratio(&devices)
+    }
+
+    /// Whether the session ratio of a controller is far off the model.
+    fn off_model(&self, source: &SourceHistory) -> bool {
+        match (self.session_ratio(source), self.expected_ratio(source)) {
+            (Some(measured), Some(expected)) => irq_model::is_off_model(measured, expected),
+            _ => false,
+        }
+    }
+
+    /// The suspend since the previous snapshot, if the system suspended.
+    fn track_suspend(&mut self, snapshot: &Snapshot) -> Option<SuspendGap> {
+        let state = snapshot.suspend.as_ref()?;
+        let gap = self
+            .prev_suspend
+            .as_ref()
+            .and_then(|prev| suspend::detect(prev, state));
+        self.prev_suspend = Some(state.clone());
+        gap
+    }
+
+    /// Track idle-state counters; `total_rate` is the interrupt rate over the same interval.
+    fn push_idle(&mut self, timestamp: f64, idle: &[IdleState], total_rate: f64) {
+        if idle.is_empty() {
+            return;
+        }
+        if let Some((prev_ts, prev)) = &self.prev_idle {
+            self.latest_idle = cpuidle::residency(prev, idle, timestamp - prev_ts);
+            // The last state is the deepest one
+            if let Some(deepest) = self.latest_idle.last() {
+                self.idle_fit.push(total_rate, deepest.residency);
+            }
+        }
+        if self.first_idle.is_none() {
+            self.first_idle = Some((timestamp, idle.to_vec()));
+        }
+        self.prev_idle = Some((timestamp, idle.to_vec()));
+    }
+
+    /// Track RAPL counters; `total_rate` is the interrupt rate over the same interval.
+    fn push_energy(&mut self, timestamp: f64, energy: &[EnergyZone], total_rate: f64) {
+        if energy.is_empty() {
+            return;
+        }
+        if let Some((prev_ts, prev)) = &self.prev_energy {
+            let elapsed = timestamp - self.start_timestamp;
+            let power = rapl::power(prev, energy, timestamp - prev_ts);
+            for zone in &power {
+                let idx = match self.power_data.iter().position(|h| h.zone == zone.zone) {
+                    Some(idx) => idx,
+                    None => {
+                        self.power_data.push(PowerHistory {
+                            zone: zone.zone.clone(),
+                            name: zone.name.clone(),
+                            data: VecDeque::with_capacity(MAX_POINTS),
+                        });
+                        self.power_data.len() - 1
+                    }
+                };
+                let history = &mut self.power_data[idx].data;
+                if history.len() >= MAX_POINTS {
+                    history.pop_front();
+                }
+                history.push_back((elapsed, zone.watts));
+            }
+            if !power.is_empty() {
+                self.energy_intervals.push((total_rate, power));
+            }
+        }
+        self.prev_energy = Some((timestamp, energy.to_vec()));
+    }
+
+    /// Average zone power during high- and low-interrupt intervals.
+    ///
+    /// Intervals above the mean total interrupt rate count as high, the
+    /// rest as low. `None` until both groups have intervals.
+    fn energy_by_load(&self) -> Option<EnergySplit> {
+        let rates: Vec<f64> = self
+            .energy_intervals
+            .iter()
+            .map(|(rate, _)| *rate)
+            .collect();
+        let mean = stats::mean(&rates)?;
+        let (high, low): (Vec<_>, Vec<_>) = self
+            .energy_intervals
+            .iter()
+            .partition(|(rate, _)| *rate > mean);
+        if high.is_empty() || low.is_empty() {
+            return None;
+        }
+
+        let mean_rate = |group: &[&(f64, Vec<ZonePower>)]| {
+            stats::mean(&group.iter().map(|(rate, _)| *rate).collect::<Vec<_>>())
+        };
+        let mean_watts = |group: &[&(f64, Vec<ZonePower>)], zone: &str| {
+            let watts: Vec<f64> = group
+                .iter()
+                .filter_map(|(_, power)| power.iter().find(|z| z.zone == zone))
+                .map(|z| z.watts)
+                .collect();
+            stats::mean(&watts)
+        };
+
+        let zones = self.energy_intervals[0]
+            .1
+            .iter()
+            .filter_map(|zone| {
+                Some((
+                    zone.name.clone(),
+                    mean_watts(&low, &zone.zone)?,
+                    mean_watts(&high, &zone.zone)?,
+                ))
+            })
+            .collect();
+        Some(EnergySplit {
+            low_rate: mean_rate(&low)?,
+            high_rate: mean_rate(&high)?,
+            low_intervals: low.len(),
+            high_intervals: high.len(),
+            zones,
+        })
+    }
+
+    /// Idle-state residency over the whole session.
+    fn session_idle(&self) -> Vec<IdleResidency> {
+        match (&self.first_idle, &self.prev_idle) {
+            (Some((first_ts, first)), Some((last_ts, last))) => {
+                cpuidle::residency(first, last, last_ts - first_ts)
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Correlation and slope of deepest-state residency against the total interrupt rate.
+    fn idle_fit(&self) -> Option<(f64, f64)> {
+        self.idle_fit.fit()
+    }
+
     /// Update with new interrupt data.
-    pub fn sample(&mut self, irq_counts: &HashMap<String, u64>) {
-        let elapsed = self.elapsed_s();
-        let interval_s = self.interval_ms as f64 / 1000.0;
+    ///
+    /// Rates use the measured time since the previous snapshot, not the
+    /// nominal interval, so late samples do not inflate them.
+    pub fn sample(&mut self, snapshot: &Snapshot) {
+        let elapsed = snapshot.timestamp - self.start_timestamp;
+        self.elapsed = elapsed;
+        let mut dt_s = snapshot.timestamp - self.prev_timestamp;
+        if dt_s <= 0.0 {
+            dt_s = self.interval_ms as f64 / 1000.0;
+        }
+        self.prev_timestamp = snapshot.timestamp;
+
+        // Counts across a suspend include suspend/resume activity, so the
+        // interval is marked instead of plotted
+        if let Some(gap) = self.track_suspend(snapshot) {
+            for source in &mut self.sources {
+                source.push_power(snapshot.power.get(&source.device));
+                if let Some(per_cpu) = snapshot.counts.get(&source.irq) {
+                    source.skip(per_cpu);
+                }
+            }
+            if !snapshot.idle.is_empty() {
+                self.prev_idle = Some((snapshot.timestamp, snapshot.idle.clone()));
+            }
+            if !snapshot.energy.is_empty() {
+                self.prev_energy = Some((snapshot.timestamp, snapshot.energy.clone()));
+            }
+            self.suspend_gaps.push((elapsed, gap));
+            return;
+        }
         let mut total_rate = 0.0;
 
         for source in &mut self.sources {
-            if let Some(&count) = irq_counts.get(&source.irq) {
-                source.push(elapsed, count, interval_s);
+            source.push_power(snapshot.power.get(&source.device));
+            if let Some(per_cpu) = snapshot.counts.get(&source.irq) {
+                source.push(elapsed, per_cpu, dt_s);
                 // Sum all sources for total (both controllers and HID devices represent real interrupts)
                 total_rate += source.latest_rate;
             }
         }
 
+        // Controller IRQs per HID IRQ, undefined while all HID devices are idle
+        for idx in 0..self.sources.len() {
+            if !self.sources[idx].is_controller {
+                continue;
+            }
+            let hid_rate = self.hid_rate(&self.sources[idx].controller);
+            let ratio = (hid_rate > 0.0).then(|| self.sources[idx].latest_rate / hid_rate);
+            self.sources[idx].push_ratio(elapsed, ratio);
+        }
+
+        if let Some(detector) = &mut self.detector {
+            let rates = self
+                .sources
+                .iter()
+                .map(|s| (s.irq.clone(), s.latest_rate))
+                .collect();
+            self.events.extend(detector.update(elapsed, dt_s, &rates));
+        }
+
+        self.push_idle(snapshot.timestamp, &snapshot.idle, total_rate);
+        self.push_energy(snapshot.timestamp, &snapshot.energy, total_rate);
+
         if self.total_history.len() >= MAX_POINTS {
             self.total_history.pop_front();
         }
@@ -329,11 +846,117 @@ impl Drop for TerminalGuard {
     }
 }
 
-/// Run the TUI dashboard.
-pub fn run(interval_ms: u64, threshold: f64) -> Result<()> {
-    // Discover topology
-    let topology = discovery::discover()?;
+/// Playback position and speed of a replayed recording.
+#[derive(Debug, Clone, Copy)]
+struct ReplayStatus {
+    position: f64,
+    duration: f64,
+    speed: f64,
+    paused: bool,
+}
 
+/// Feeds the snapshots of a recording into the app at a chosen speed.
+struct Replay {
+    topology: I2cTopology,
+    snapshots: Vec<Snapshot>,
+    /// Index of the next snapshot to feed
+    next: usize,
+    /// Playback position, relative to the first snapshot
+    position: f64,
+    speed: f64,
+    paused: bool,
+}
+
+impl Replay {
+    fn new(recording: Recording, speed: f64) -> Result<Self> {
+        let topology = recording.topology()?.clone();
+        if recording.snapshots.is_empty() {
+            anyhow::bail!("recording contains no snapshots");
+        }
+        Ok(Self {
+            topology,
+            snapshots: recording.snapshots,
+            next: 1,
+            position: 0.0,
+            speed: speed.clamp(MIN_SPEED, MAX_SPEED),
+            paused: false,
+        })
+    }
+
+    fn start_timestamp(&self) -> f64 {
+        self.snapshots[0].timestamp
+    }
+
+    fn duration(&self) -> f64 {
+        self.snapshots[self.snapshots.len() - 1].timestamp - self.start_timestamp()
+    }
+
+    fn status(&self) -> ReplayStatus {
+        ReplayStatus {
+            position: self.position,
+            duration: self.duration(),
+            speed: self.speed,
+            paused: self.paused,
+        }
+    }
+
+    /// Move the playback position forward by `real_dt` seconds of wall time.
+    fn advance(&mut self, app: &mut App, real_dt: f64) {
+        if !self.paused {
+            self.position = (self.position + real_dt * self.speed).min(self.duration());
+        }
+        self.feed(app);
+    }
+
+    /// Feed every snapshot up to the playback position.
+    fn feed(&mut self, app: &mut App) {
+        let start = self.start_timestamp();
+        while let Some(snapshot) = self.snapshots.get(self.next) {
+            if snapshot.timestamp - start > self.position {
+                break;
+            }
+            app.sample(snapshot);
+            self.next += 1;
+        }
+    }
+
+    /// Jump to a position by rebuilding the app state from the first snapshot.
+    fn seek(&mut self, app: &mut App, position: f64) {
+        self.position = position.clamp(0.0, self.duration());
+        app.reset(&self.topology, &self.snapshots[0]);
+        self.next = 1;
+        self.feed(app);
+    }
+
+    /// Wall time until the next snapshot is due, if playing.
+    fn until_next(&self) -> Option<Duration> {
+        if self.paused {
+            return None;
+        }
+        let snapshot = self.snapshots.get(self.next)?;
+        let due = snapshot.timestamp - self.start_timestamp() - self.position;
+        Some(Duration::from_secs_f64((due / self.speed).max(0.0)))
+    }
+
+    /// Handle a playback key, returning false if the key is not a playback key.
+    fn handle_key(&mut self, app: &mut App, code: KeyCode) -> bool {
+        match code {
+            KeyCode::Char('p') => self.paused = !self.paused,
+            KeyCode::Char('+') | KeyCode::Char('=') => {
+                self.speed = (self.speed * 2.0).min(MAX_SPEED);
+            }
+            KeyCode::Char('-') => self.speed = (self.speed / 2.0).max(MIN_SPEED),
+            KeyCode::Right => self.seek(app, self.position + SEEK_STEP_S),
+            KeyCode::Left => self.seek(app, self.position - SEEK_STEP_S),
+            KeyCode::Home => self.seek(app, 0.0),
+            KeyCode::End => self.seek(app, self.duration()),
+            _ => return false,
+        }
+        true
+    }
+}
+
+fn check_topology(topology: &I2cTopology) -> Result<()> {
     if topology.controllers.is_empty() {
         anyhow::bail!(
             "No I2C controllers with HID devices found.\n\
@@ -343,34 +966,59 @@ pub fn run(interval_ms: u64, threshold: f64) -> Result<()> {
              - The I2C controller uses a different driver"
         );
     }
+    Ok(())
+}
+
+/// Run the TUI dashboard.
+pub fn run(
+    interval_ms: u64,
+    thresholds: Thresholds,
+    speed: f64,
+    fifo: FifoConfig,
+    min_duration_s: f64,
+    sample_source: &SampleSource,
+    roots: &FsRoots,
+) -> Result<()> {
+    let mut app = App::new(interval_ms, thresholds.default_warn);
+    app.thresholds = thresholds;
+    app.fifo = fifo;
+    app.min_duration_s = min_duration_s;
+    match sample_source {
+        SampleSource::Live(backend) => run_live(app, *backend, roots),
+        SampleSource::Replay(path) => {
+            let recording = Recording::load(path)?;
+            if let Some(interval_ms) = recording.header.interval_ms {
+                app.interval_ms = interval_ms;
+            }
+            run_replay(app, Replay::new(recording, speed)?)
+        }
+    }
+}
+
+fn run_live(mut app: App, backend: Backend, roots: &FsRoots) -> Result<()> {
+    // Discover topology
+    let topology = crate::discovery::discover(roots)?;
+    check_topology(&topology)?;
 
     // Get initial interrupt counts
-    let initial_sources = interrupts::read_interrupts()?;
-    let initial_counts: HashMap<String, u64> = initial_sources
-        .iter()
-        .map(|s| (s.irq.clone(), s.count))
-        .collect();
+    let mut sampler = backend.open_with_power(&topology, roots);
+    let initial = sampler
+        .sample()?
+        .context("no interrupt snapshots available")?;
 
-    let mut app = App::new(interval_ms, threshold);
-    app.init_from_topology(&topology, &initial_counts);
+    app.init_from_topology(&topology, &initial);
 
     if app.sources.is_empty() {
         anyhow::bail!("No interrupt sources found for the discovered I2C devices.");
     }
 
     let mut guard = TerminalGuard::new()?;
-    let interval_duration = Duration::from_millis(interval_ms);
-    let mut next_sample = Instant::now() + interval_duration;
+    let mut schedule = Schedule::new(Duration::from_millis(app.interval_ms));
 
     while !app.should_quit {
         guard.terminal.draw(|frame| ui(frame, &app))?;
 
-        let now = Instant::now();
-        let timeout = if next_sample > now {
-            next_sample - now
-        } else {
-            Duration::ZERO
-        };
+        let timeout = schedule.until_next(Instant::now());
 
         if event::poll(timeout).context("event poll failed")?
             && let Event::Key(key) = event::read().context("event read failed")?
@@ -379,12 +1027,14 @@ pub fn run(interval_ms: u64, threshold: f64) -> Result<()> {
             handle_key(&mut app, key.code);
         }
 
-        if Instant::now() >= next_sample {
-            let sources = interrupts::read_interrupts()?;
-            let counts: HashMap<String, u64> =
-                sources.iter().map(|s| (s.irq.clone(), s.count)).collect();
-            app.sample(&counts);
-            next_sample = Instant::now() + interval_duration;
+        let now = Instant::now();
+        if now >= schedule.deadline() {
+            if let Some(snapshot) = sampler.sample()? {
+                app.sample(&snapshot);
+            }
+            schedule.advance(now);
+            app.late_samples = schedule.late;
+            app.missed_samples = schedule.missed;
         }
     }
 
@@ -394,46 +1044,204 @@ pub fn run(interval_ms: u64, threshold: f64) -> Result<()> {
     Ok(())
 }
 
+/// Replay a recording without touching the live system.
+fn run_replay(mut app: App, mut replay: Replay) -> Result<()> {
+    check_topology(&replay.topology)?;
+
+    app.init_from_topology(&replay.topology, &replay.snapshots[0]);
+
+    if app.sources.is_empty() {
+        anyhow::bail!("No interrupt sources found in the recording.");
+    }
+
+    let mut guard = TerminalGuard::new()?;
+    let max_wait = Duration::from_millis(250);
+    let mut last_tick = Instant::now();
+
+    while !app.should_quit {
+        app.replay = Some(replay.status());
+        guard.terminal.draw(|frame| ui(frame, &app))?;
+
+        let timeout = replay.until_next().unwrap_or(max_wait).min(max_wait);
+
+        if event::poll(timeout).context("event poll failed")?
+            && let Event::Key(key) = event::read().context("event read failed")?
+            && key.kind == KeyEventKind::Press
+            && !replay.handle_key(&mut app, key.code)
+        {
+            handle_key(&mut app, key.code);
+        }
+
+        let now = Instant::now();
+        replay.advance(&mut app, (now - last_tick).as_secs_f64());
+        last_tick = now;
+    }
+
+    drop(guard);
+    print_summary(&app);
+
+    Ok(())
+}
+
 fn handle_key(app: &mut App, code: KeyCode) {
     match code {
         KeyCode::Char('q') | KeyCode::Esc => app.should_quit = true,
         KeyCode::Up | KeyCode::Char('k') => app.select_prev(),
         KeyCode::Down | KeyCode::Char('j') => app.select_next(),
         KeyCode::Char(' ') => app.toggle_visibility(),
+        KeyCode::Char('c') => app.show_cpu_panel = !app.show_cpu_panel,
+        KeyCode::Char('i') => app.show_idle_panel = !app.show_idle_panel,
+        KeyCode::Char('w') => app.show_power_panel = !app.show_power_panel,
+        KeyCode::Char('e') => app.show_event_panel = !app.show_event_panel,
+        KeyCode::Char('r') => app.toggle_chart_mode(),
         _ => {}
     }
 }
 
 fn ui(frame: &mut Frame, app: &App) {
     let table_height = (app.sources.len() + 4) as u16;
+    let event_height = if app.show_event_panel && !app.events.is_empty() {
+        (app.events.len().min(MAX_EVENT_LINES) + 2) as u16
+    } else {
+        0
+    };
 
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Min(10),
             Constraint::Length(table_height.min(15)),
+            Constraint::Length(event_height),
             Constraint::Length(1),
         ])
         .split(frame.area());
 
-    render_chart(frame, app, chunks[0]);
+    let show_idle = app.show_idle_panel && !app.latest_idle.is_empty();
+    if app.show_cpu_panel || show_idle {
+        let chart_chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(65), Constraint::Percentage(35)])
+            .split(chunks[0]);
+        render_charts(frame, app, chart_chunks[0]);
+
+        match (app.show_cpu_panel, show_idle) {
+            (true, true) => {
+                let idle_height = (app.latest_idle.len() + 3) as u16;
+                let side = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Min(8), Constraint::Length(idle_height)])
+                    .split(chart_chunks[1]);
+                render_cpu_chart(frame, app, side[0]);
+                render_idle_panel(frame, app, side[1]);
+            }
+            (true, false) => render_cpu_chart(frame, app, chart_chunks[1]),
+            _ => render_idle_panel(frame, app, chart_chunks[1]),
+        }
+    } else {
+        render_charts(frame, app, chunks[0]);
+    }
     render_table(frame, app, chunks[1]);
-    render_status_bar(frame, app, chunks[2]);
+    if event_height > 0 {
+        render_event_panel(frame, app, chunks[2]);
+    }
+    render_status_bar(frame, app, chunks[3]);
+}
+
+/// The interrupt chart, with the power chart below it on the same time axis.
+fn render_charts(frame: &mut Frame, app: &App, area: Rect) {
+    if app.show_power_panel && !app.power_data.is_empty() {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+            .split(area);
+        render_chart(frame, app, rows[0]);
+        render_power_chart(frame, app, rows[1]);
+    } else {
+        render_chart(frame, app, area);
+    }
+}
+
+fn render_power_chart(frame: &mut Frame, app: &App, area: Rect) {
+    let x_bounds = app.x_bounds();
+    let data_vecs: Vec<Vec<(f64, f64)>> = app
+        .power_data
+        .iter()
+        .map(|h| h.data.iter().copied().collect())
+        .collect();
+    let max_watts = data_vecs
+        .iter()
+        .flatten()
+        .map(|&(_, watts)| watts)
+        .fold(0.0f64, f64::max);
+    let y_max = axis_max(max_watts);
+
+    let datasets: Vec<Dataset> = app
+        .power_data
+        .iter()
+        .zip(&data_vecs)
+        .enumerate()
+        .map(|(i, (history, data))| {
+            Dataset::default()
+                .name(history.name.as_str())
+                .marker(Marker::Braille)
+                .graph_type(ratatui::widgets::GraphType::Line)
+                .style(Style::default().fg(POWER_COLORS[i % POWER_COLORS.len()]))
+                .data(data)
+        })
+        .collect();
+
+    let x_labels = vec![
+        Span::raw(format!("{:.0}s", x_bounds[0])),
+        Span::raw(format!("{:.0}s", (x_bounds[0] + x_bounds[1]) / 2.0)),
+        Span::raw(format!("{:.0}s", x_bounds[1])),
+    ];
+
+    let chart = Chart::new(datasets)
+        .block(
+            Block::default()
+                .title(" Power (RAPL) ")
+                .borders(Borders::ALL),
+        )
+        .x_axis(
+            Axis::default()
+                .title("Time")
+                .bounds(x_bounds)
+                .labels(x_labels),
+        )
+        .y_axis(
+            Axis::default()
+                .title("Watts")
+                .bounds([0.0, y_max])
+                .labels(app.y_labels(y_max, "W")),
+        );
+    frame.render_widget(chart, area);
 }
 
 fn render_chart(frame: &mut Frame, app: &App, area: Rect) {
     let x_bounds = app.x_bounds();
     let y_max = app.y_max();
 
-    // Collect data for all sources
+    // Collect data for all sources (only controllers have a ratio series)
     let data_vecs: Vec<Vec<(f64, f64)>> = app
         .sources
         .iter()
-        .map(|s| s.data.iter().copied().collect())
+        .map(|s| match app.chart_mode {
+            ChartMode::Rates => s.data.iter().copied().collect(),
+            ChartMode::Ratio => s.ratio_data.iter().copied().collect(),
+        })
         .collect();
 
     let total_data_vec: Vec<(f64, f64)> = app.total_history.iter().copied().collect();
 
+    // Suspends are marked with a vertical line at the resume
+    let suspend_marks: Vec<[(f64, f64); 2]> = app
+        .suspend_gaps
+        .iter()
+        .map(|&(t, _)| t)
+        .filter(|t| *t >= x_bounds[0] && *t <= x_bounds[1])
+        .map(|t| [(t, 0.0), (t, y_max)])
+        .collect();
+
     // Build datasets - use the source's assigned color
     let mut datasets: Vec<Dataset> = Vec::new();
 
@@ -451,7 +1259,7 @@ fn render_chart(frame: &mut Frame, app: &App, area: Rect) {
         );
     }
 
-    if app.total_visible && !total_data_vec.is_empty() {
+    if app.total_visible && app.chart_mode == ChartMode::Rates && !total_data_vec.is_empty() {
         datasets.push(
             Dataset::default()
                 .name("TOTAL")
@@ -466,15 +1274,43 @@ fn render_chart(frame: &mut Frame, app: &App, area: Rect) {
         );
     }
 
+    for (i, mark) in suspend_marks.iter().enumerate() {
+        let dataset = Dataset::default()
+            .marker(Marker::Braille)
+            .graph_type(ratatui::widgets::GraphType::Line)
+            .style(Style::default().fg(Color::DarkGray))
+            .data(mark);
+        datasets.push(if i == 0 {
+            dataset.name("suspend")
+        } else {
+            dataset
+        });
+    }
+
     let x_labels = vec![
         Span::raw(format!("{:.0}s", x_bounds[0])),
         Span::raw(format!("{:.0}s", (x_bounds[0] + x_bounds[1]) / 2.0)),
         Span::raw(format!("{:.0}s", x_bounds[1])),
     ];
-    let y_labels = app.y_labels(y_max);
-
-    let title = if app.threshold > 0.0 {
-        format!(" Interrupt Monitor (threshold: {:.0}/s) ", app.threshold)
+    let (y_title, y_unit) = match app.chart_mode {
+        ChartMode::Rates => ("Interrupts/s", "/s"),
+        ChartMode::Ratio => ("Ctrl IRQs per HID IRQ", ""),
+    };
+    let y_labels = app.y_labels(y_max, y_unit);
+
+    let title = if app.chart_mode == ChartMode::Ratio {
+        " Controller/HID Interrupt Ratio (gaps: HID idle) ".to_string()
+    } else if !app.thresholds.rules.is_empty() {
+        format!(
+            " Interrupt Monitor (threshold: {:.0}/s, {} per-source limits) ",
+            app.thresholds.default_warn,
+            app.thresholds.rules.len()
+        )
+    } else if app.thresholds.default_warn > 0.0 {
+        format!(
+            " Interrupt Monitor (threshold: {:.0}/s) ",
+            app.thresholds.default_warn
+        )
     } else {
         " Interrupt Monitor ".to_string()
     };
@@ -489,7 +1325,7 @@ fn render_chart(frame: &mut Frame, app: &App, area: Rect) {
         )
         .y_axis(
             Axis::default()
-                .title("Interrupts/s")
+                .title(y_title)
                 .bounds([0.0, y_max])
                 .labels(y_labels),
         );
@@ -497,10 +1333,133 @@ fn render_chart(frame: &mut Frame, app: &App, area: Rect) {
     frame.render_widget(chart, area);
 }
 
+/// Render the per-CPU rates of the selected source.
+fn render_cpu_chart(frame: &mut Frame, app: &App, area: Rect) {
+    let Some(source) = app.sources.get(app.selected_idx) else {
+        let hint = Paragraph::new(" Select a source to see its per-CPU rates")
+            .style(Style::default().fg(Color::DarkGray))
+            .block(Block::default().title(" Per-CPU ").borders(Borders::ALL));
+        frame.render_widget(hint, area);
+        return;
+    };
+
+    // Only plot CPUs that took this interrupt within the window
+    let active: Vec<(String, Vec<(f64, f64)>)> = source
+        .cpu_data
+        .iter()
+        .filter(|(_, data)| data.iter().any(|&(_, rate)| rate > 0.0))
+        .map(|(cpu, data)| (format!("CPU{}", cpu), data.iter().copied().collect()))
+        .collect();
+
+    let max_rate = active
+        .iter()
+        .flat_map(|(_, data)| data.iter().map(|&(_, rate)| rate))
+        .fold(0.0f64, f64::max);
+    let y_max = axis_max(max_rate);
+
+    let datasets: Vec<Dataset> = active
+        .iter()
+        .enumerate()
+        .map(|(i, (name, data))| {
+            Dataset::default()
+                .name(name.as_str())
+                .marker(Marker::Braille)
+                .graph_type(ratatui::widgets::GraphType::Line)
+                .style(Style::default().fg(CPU_COLORS[i % CPU_COLORS.len()]))
+                .data(data)
+        })
+        .collect();
+
+    let x_bounds = app.x_bounds();
+    let x_labels = vec![
+        Span::raw(format!("{:.0}s", x_bounds[0])),
+        Span::raw(format!("{:.0}s", x_bounds[1])),
+    ];
+
+    let title = format!(
+        " IRQ {} per CPU ({}/{} active) ",
+        source.irq,
+        active.len(),
+        source.cpu_data.len()
+    );
+
+    let chart = Chart::new(datasets)
+        .block(Block::default().title(title).borders(Borders::ALL))
+        .x_axis(Axis::default().bounds(x_bounds).labels(x_labels))
+        .y_axis(
+            Axis::default()
+                .bounds([0.0, y_max])
+                .labels(app.y_labels(y_max, "/s")),
+        );
+
+    frame.render_widget(chart, area);
+}
+
+fn render_idle_panel(frame: &mut Frame, app: &App, area: Rect) {
+    let header = Row::new(vec!["State", "Resid", "Entries/s"])
+        .style(Style::default().add_modifier(Modifier::BOLD));
+    let rows: Vec<Row> = app
+        .latest_idle
+        .iter()
+        .map(|state| {
+            Row::new(vec![
+                state.name.clone(),
+                format!("{:.1}%", state.residency),
+                format!("{:.0}", state.entry_rate),
+            ])
+        })
+        .collect();
+
+    // Correlation of the deepest state's residency with the interrupt rate
+    let fit = match app.idle_fit() {
+        Some((r, slope)) => format!(" r={:+.2}, {:+.1}pp per 1k irq/s ", r, slope * 1000.0),
+        None => " r=n/a ".to_string(),
+    };
+    let widths = [
+        Constraint::Min(8),
+        Constraint::Length(7),
+        Constraint::Length(10),
+    ];
+    let table = Table::new(rows, widths).header(header).block(
+        Block::default()
+            .title(" C-states ")
+            .title_bottom(fit)
+            .borders(Borders::ALL),
+    );
+    frame.render_widget(table, area);
+}
+
+/// The latest storm and stuck-source events, newest last.
+fn render_event_panel(frame: &mut Frame, app: &App, area: Rect) {
+    let visible = (area.height as usize).saturating_sub(2);
+    let start = app.events.len().saturating_sub(visible);
+    let lines: Vec<Line> = app.events[start..]
+        .iter()
+        .map(|event| {
+            let color = match event.phase {
+                Phase::Start => Color::Red,
+                Phase::End => Color::DarkGray,
+            };
+            Line::styled(
+                format!("{:>7.1}s  {}", event.timestamp, event.describe()),
+                Style::default().fg(color),
+            )
+        })
+        .collect();
+
+    let active = app.detector.as_ref().map_or(0, |d| d.active().count());
+    let title = format!(" Events ({} logged, {} active) ", app.events.len(), active);
+    let panel = Paragraph::new(lines).block(Block::default().title(title).borders(Borders::ALL));
+    frame.render_widget(panel, area);
+}
+
 fn render_table(frame: &mut Frame, app: &App, area: Rect) {
-    let header = Row::new(vec!["", "Source", "Type", "IRQ", "Rate", "Avg", "Max"])
-        .style(Style::default().add_modifier(Modifier::BOLD))
-        .bottom_margin(0);
+    let header = Row::new(vec![
+        "", "Source", "Type", "IRQ", "Chip", "Trigger", "Rate", "Avg", "Max", "Ratio", "Expect",
+        "Active",
+    ])
+    .style(Style::default().add_modifier(Modifier::BOLD))
+    .bottom_margin(0);
 
     let mut rows: Vec<Row> = Vec::new();
 
@@ -528,13 +1487,40 @@ fn render_table(frame: &mut Frame, app: &App, area: Rect) {
         };
 
         let mut style = Style::default().fg(color);
-        if source.latest_rate > app.threshold && app.threshold > 0.0 && source.visible {
-            style = style.bg(Color::DarkGray);
+        if source.visible {
+            style = match source.level.level {
+                Level::Ok => style,
+                Level::Warn => style.bg(Color::DarkGray),
+                Level::Critical => style
+                    .fg(Color::White)
+                    .bg(Color::Red)
+                    .add_modifier(Modifier::BOLD),
+            };
         }
         if is_selected {
             style = style.add_modifier(Modifier::REVERSED);
         }
 
+        let ratio_str = if source.is_controller {
+            format_ratio(
+                source.latest_ratio,
+                app.hid_sources(&source.controller).count(),
+            )
+        } else {
+            String::new()
+        };
+        // Flag controllers whose session ratio is far off the FIFO model
+        let expected_str = match app.expected_ratio(source) {
+            Some(expected) if app.off_model(source) => format!("{:.1}:1 !", expected),
+            Some(expected) => format!("{:.1}:1", expected),
+            None => String::new(),
+        };
+
+        let active_str = source
+            .active_percent()
+            .map(|p| format!("{:.0}%", p))
+            .unwrap_or_else(|| "-".to_string());
+
         // Show hierarchy with indentation
         let display_name = source.display_name();
         let type_str = if source.is_controller {
@@ -549,9 +1535,14 @@ fn render_table(frame: &mut Frame, app: &App, area: Rect) {
                 display_name,
                 type_str,
                 format!("IRQ {}", source.irq),
+                source.chip.clone().unwrap_or_else(|| "-".to_string()),
+                source.trigger.clone().unwrap_or_else(|| "-".to_string()),
                 rate_str,
                 avg_str,
                 max_str,
+                ratio_str,
+                expected_str,
+                active_str,
             ])
             .style(style),
         );
@@ -589,9 +1580,14 @@ fn render_table(frame: &mut Frame, app: &App, area: Rect) {
             "TOTAL".to_string(),
             String::new(),
             String::new(),
+            String::new(),
+            String::new(),
             format!("{:.1}/s", app.total_latest),
             format!("{:.1}/s", total_avg),
             total_max_str,
+            String::new(),
+            String::new(),
+            String::new(),
         ])
         .style(total_style),
     );
@@ -601,9 +1597,14 @@ fn render_table(frame: &mut Frame, app: &App, area: Rect) {
         Constraint::Min(35),
         Constraint::Length(15),
         Constraint::Length(8),
+        Constraint::Length(12),
+        Constraint::Length(8),
         Constraint::Length(10),
         Constraint::Length(10),
         Constraint::Length(10),
+        Constraint::Length(8),
+        Constraint::Length(9),
+        Constraint::Length(7),
     ];
 
     let table = Table::new(rows, widths)
@@ -613,12 +1614,42 @@ fn render_table(frame: &mut Frame, app: &App, area: Rect) {
     frame.render_widget(table, area);
 }
 
+/// Format a controller-to-HID ratio, e.g. "29.6:1".
+///
+/// Controllers without HID interrupt sources show "-", and idle periods where
+/// no HID device fired show "idle".
+fn format_ratio(ratio: Option<f64>, hid_sources: usize) -> String {
+    match ratio {
+        _ if hid_sources == 0 => "-".to_string(),
+        Some(ratio) => format!("{:.1}:1", ratio),
+        None => "idle".to_string(),
+    }
+}
+
 fn render_status_bar(frame: &mut Frame, app: &App, area: Rect) {
     let elapsed = app.elapsed_s();
-    let text = format!(
-        " [q]uit [j/k]sel [space]hide | {:.0}s {}ms #{}",
-        elapsed, app.interval_ms, app.sample_count,
-    );
+    let text = match app.replay {
+        Some(replay) => format!(
+            " [q]uit [j/k]sel [space]hide [c]pu [i]dle [w]atts [e]vents [r]atio [p]ause [\u{2190}/\u{2192}]seek [+/-]speed | REPLAY {:.0}s/{:.0}s {}x{} #{}",
+            replay.position,
+            replay.duration,
+            replay.speed,
+            if replay.paused { " PAUSED" } else { "" },
+            app.sample_count,
+        ),
+        None => format!(
+            " [q]uit [j/k]sel [space]hide [c]pu [i]dle [w]atts [e]vents [r]atio | {:.0}s {}ms #{} late {} missed {}{}",
+            elapsed,
+            app.interval_ms,
+            app.sample_count,
+            app.late_samples,
+            app.missed_samples,
+            match app.suspend_gaps.last() {
+                Some((_, gap)) => format!(" | last {}", gap.describe()),
+                None => String::new(),
+            },
+        ),
+    };
     let bar = Paragraph::new(text).style(Style::default().fg(Color::DarkGray));
     frame.render_widget(bar, area);
 }
@@ -669,9 +1700,424 @@ fn print_summary(app: &App) {
         "TOTAL", total_avg, total_max
     );
 
+    println!("\n=== Thresholds ===\n");
     println!(
-        "\nSamples: {} over {:.1}s\n",
+        "{:<40} {:>10} {:>10} {:>10} {:>10} {:>8}",
+        "Source", "Warn", "Critical", "At warn", "At crit", "Peak"
+    );
+    println!("{}", "-".repeat(93));
+    for source in &app.sources {
+        let level = &source.level;
+        let critical = level
+            .limit
+            .critical
+            .map(|c| format!("{:.0}/s", c))
+            .unwrap_or_else(|| "-".to_string());
+        let peak = match level.peak {
+            Level::Ok => "ok",
+            Level::Warn => "WARN",
+            Level::Critical => "CRIT",
+        };
+        println!(
+            "{:<40} {:>8.0}/s {:>10} {:>9.1}s {:>9.1}s {:>8}",
+            source.display_name(),
+            level.limit.warn,
+            critical,
+            level.warn_s,
+            level.critical_s,
+            peak
+        );
+    }
+
+    let controllers: Vec<_> = app.sources.iter().filter(|s| s.is_controller).collect();
+    if !controllers.is_empty() {
+        println!("\n=== Controller/HID Interrupt Ratio ===\n");
+        println!(
+            "{:<40} {:>12} {:>12}   (RX FIFO depth {}, threshold {})",
+            "Controller", "Measured", "Expected", app.fifo.depth, app.fifo.rx_threshold
+        );
+        println!("{}", "-".repeat(80));
+        for source in controllers {
+            let hid_sources = app.hid_sources(&source.controller).count();
+            let ratio = match app.session_ratio(source) {
+                None if hid_sources > 0 => "n/a (HID devices idle)".to_string(),
+                ratio => format_ratio(ratio, hid_sources),
+            };
+            let expected = app
+                .expected_ratio(source)
+                .map(|e| format!("{:.1}:1", e))
+                .unwrap_or_else(|| "-".to_string());
+            let flag = if app.off_model(source) {
+                "  ** off model"
+            } else {
+                ""
+            };
+            println!(
+                "{:<40} {:>12} {:>12}{}",
+                source.controller, ratio, expected, flag
+            );
+        }
+    }
+
+    let idle = app.session_idle();
+    if !idle.is_empty() {
+        println!("\n=== CPU Idle States ===\n");
+        println!("{:<40} {:>12} {:>12}", "State", "Residency", "Entries/s");
+        println!("{}", "-".repeat(66));
+        for state in &idle {
+            println!(
+                "{:<40} {:>11.1}% {:>12.0}",
+                state.name, state.residency, state.entry_rate
+            );
+        }
+        if let Some(deepest) = idle.last() {
+            match app.idle_fit() {
+                Some((r, slope)) => println!(
+                    "\n{} residency vs interrupt rate: r = {:+.2}, {:+.1} percentage points per 1000 irqs/s",
+                    deepest.name,
+                    r,
+                    slope * 1000.0
+                ),
+                None => println!(
+                    "\n{} residency vs interrupt rate: n/a (too few samples or no variation)",
+                    deepest.name
+                ),
+            }
+        }
+    }
+
+    if let Some((_, zones)) = app.energy_intervals.last() {
+        println!("\n=== Power (RAPL) ===\n");
+        match app.energy_by_load() {
+            Some(split) => {
+                println!(
+                    "Low-interrupt intervals:  {:>4} (avg {:.1} irqs/s)",
+                    split.low_intervals, split.low_rate
+                );
+                println!(
+                    "High-interrupt intervals: {:>4} (avg {:.1} irqs/s)\n",
+                    split.high_intervals, split.high_rate
+                );
+                println!(
+                    "{:<40} {:>10} {:>10} {:>12} {:>16}",
+                    "Zone", "Low", "High", "Difference", "Per 1000 irqs/s"
+                );
+                println!("{}", "-".repeat(92));
+                let rate_diff = split.high_rate - split.low_rate;
+                for (name, low, high) in &split.zones {
+                    let diff_mw = (high - low) * 1000.0;
+                    println!(
+                        "{:<40} {:>9.2}W {:>9.2}W {:>+10.0}mW {:>+14.0}mW",
+                        name,
+                        low,
+                        high,
+                        diff_mw,
+                        diff_mw / rate_diff * 1000.0
+                    );
+                }
+            }
+            None => {
+                println!("Interrupt rate did not vary enough to compare high and low intervals.");
+                for zone in zones {
+                    println!("{:<40} {:>9.2}W (latest)", zone.name, zone.watts);
+                }
+            }
+        }
+    }
+
+    let with_power: Vec<_> = app
+        .sources
+        .iter()
+        .filter(|s| s.latest_power.is_some())
+        .collect();
+    if !with_power.is_empty() {
+        println!("\n=== Runtime PM ===\n");
+        println!(
+            "{:<40} {:>12} {:>8} {:>8} {:>8} {:>8}",
+            "Device", "Status", "Control", "Active", "Wakeup", "Wakeups"
+        );
+        println!("{}", "-".repeat(90));
+        for source in with_power {
+            let Some(power) = &source.latest_power else {
+                continue;
+            };
+            let active = source
+                .active_percent()
+                .map(|p| format!("{:.1}%", p))
+                .unwrap_or_else(|| "-".to_string());
+            let wakeups = source
+                .wakeups()
+                .map(|w| w.to_string())
+                .unwrap_or_else(|| "-".to_string());
+            println!(
+                "{:<40} {:>12} {:>8} {:>8} {:>8} {:>8}",
+                source.display_name(),
+                power.runtime_status.as_deref().unwrap_or("-"),
+                power.control.as_deref().unwrap_or("-"),
+                active,
+                power.wakeup.as_deref().unwrap_or("-"),
+                wakeups
+            );
+        }
+    }
+
+    if !app.suspend_gaps.is_empty() {
+        println!("\n=== System Suspends ===\n");
+        for (elapsed, gap) in &app.suspend_gaps {
+            println!("at {:>8.1}s: {}", elapsed, gap.describe());
+        }
+        let known: Vec<bool> = app
+            .suspend_gaps
+            .iter()
+            .filter_map(|(_, gap)| gap.reached_s0ix())
+            .collect();
+        if !known.is_empty() {
+            println!(
+                "\nS0ix reached in {} of {} suspends with residency data",
+                known.iter().filter(|&&reached| reached).count(),
+                known.len()
+            );
+        }
+    }
+
+    if !app.events.is_empty() {
+        println!("\n=== Events ===\n");
+        for event in &app.events {
+            println!("at {:>8.1}s: {}", event.timestamp, event.describe());
+        }
+    }
+
+    print!(
+        "\nSamples: {} over {:.1}s",
         app.sample_count,
         app.elapsed_s()
     );
+    if app.replay.is_none() {
+        print!(
+            " ({} late, {} missed)",
+            app.late_samples, app.missed_samples
+        );
+    }
+    println!("\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::fixture;
+
+    fn topology() -> I2cTopology {
+        fixture::touchpad_topology()
+    }
+
+    fn snapshot(timestamp: f64, controller: u64, hid: u64) -> Snapshot {
+        fixture::snapshot(
+            timestamp,
+            &[("21", &[(0, controller)]), ("203", &[(0, hid)])],
+        )
+    }
+
+    #[test]
+    fn test_controller_hid_ratio() {
+        let topology = topology();
+        let mut app = App::new(1000, 100.0);
+        app.init_from_topology(&topology, &snapshot(0.0, 0, 0));
+
+        app.sample(&snapshot(1.0, 3000, 100));
+        assert_eq!(app.sources[0].latest_ratio, Some(30.0));
+
+        // Controller traffic while the touchpad is idle has no defined ratio
+        app.sample(&snapshot(2.0, 3010, 100));
+        assert_eq!(app.sources[0].latest_ratio, None);
+        assert_eq!(app.sources[0].ratio_data.len(), 1);
+
+        assert_eq!(app.session_ratio(&app.sources[0]), Some(30.1));
+        assert_eq!(app.session_ratio(&app.sources[1]), None);
+    }
+
+    #[test]
+    fn test_expected_ratio_from_fifo_model() {
+        let mut topology = topology();
+        topology.controllers[0].hid_devices[0].max_input_report = Some(30);
+        let mut app = App::new(1000, 100.0);
+        app.init_from_topology(&topology, &snapshot(0.0, 0, 0));
+
+        // 32 RX_FULL + 1 TX_EMPTY + 1 STOP_DET per report at threshold 0
+        app.sample(&snapshot(1.0, 3000, 100));
+        assert_eq!(app.expected_ratio(&app.sources[0]), Some(34.0));
+        assert!(!app.off_model(&app.sources[0]));
+        assert_eq!(app.expected_ratio(&app.sources[1]), None);
+
+        // A raised RX threshold predicts far fewer interrupts than measured
+        app.fifo = FifoConfig {
+            depth: 64,
+            rx_threshold: 31,
+        };
+        assert_eq!(app.expected_ratio(&app.sources[0]), Some(3.0));
+        assert!(app.off_model(&app.sources[0]));
+    }
+
+    #[test]
+    fn test_runtime_pm_active_time() {
+        let topology = topology();
+        let power = |active: u64, suspended: u64| PowerState {
+            runtime_status: Some("suspended".to_string()),
+            runtime_active_time: Some(active),
+            runtime_suspended_time: Some(suspended),
+            wakeup_count: Some(active / 1000),
+            ..Default::default()
+        };
+        let with_power = |timestamp: f64, active: u64, suspended: u64| {
+            let mut snapshot = snapshot(timestamp, 0, 0);
+            snapshot.power =
+                HashMap::from([("i2c_designware.5".to_string(), power(active, suspended))]);
+            snapshot
+        };
+
+        let mut app = App::new(1000, 100.0);
+        app.init_from_topology(&topology, &with_power(0.0, 5000, 20000));
+        app.sample(&with_power(1.0, 5250, 20750));
+        app.sample(&with_power(2.0, 6000, 21000));
+
+        // 1000ms active out of 2000ms since the first snapshot
+        assert_eq!(app.sources[0].active_percent(), Some(50.0));
+        assert_eq!(app.sources[0].wakeups(), Some(1));
+        assert_eq!(app.sources[1].active_percent(), None);
+    }
+
+    #[test]
+    fn test_idle_residency_tracks_interrupt_rate() {
+        let topology = topology();
+        // One CPU; C10 residency drops as the controller fires more
+        let with_idle = |timestamp: f64, controller: u64, c10_us: u64| {
+            let mut snapshot = snapshot(timestamp, controller, 0);
+            snapshot.idle = vec![IdleState {
+                name: "C10".to_string(),
+                usage: controller,
+                time_us: c10_us,
+                cpus: 1,
+            }];
+            snapshot
+        };
+
+        let mut app = App::new(1000, 100.0);
+        app.init_from_topology(&topology, &with_idle(0.0, 0, 0));
+        app.sample(&with_idle(1.0, 0, 900_000));
+        app.sample(&with_idle(2.0, 1000, 1_650_000));
+        app.sample(&with_idle(3.0, 3000, 2_250_000));
+
+        assert_eq!(app.latest_idle[0].residency, 60.0);
+        assert_eq!(app.latest_idle[0].entry_rate, 2000.0);
+        let (r, slope) = app.idle_fit().unwrap();
+        assert!(r < -0.99);
+        assert!((slope * 1000.0 + 15.0).abs() < 1e-9);
+
+        let session = app.session_idle();
+        assert_eq!(session[0].residency, 75.0);
+    }
+
+    #[test]
+    fn test_power_during_high_and_low_interrupt_intervals() {
+        let topology = topology();
+        let with_energy = |timestamp: f64, controller: u64, energy_uj: u64| {
+            let mut snapshot = snapshot(timestamp, controller, 0);
+            snapshot.energy = vec![EnergyZone {
+                zone: "intel-rapl:0".to_string(),
+                name: "package-0".to_string(),
+                energy_uj,
+                max_energy_range_uj: Some(10_000_000),
+            }];
+            snapshot
+        };
+
+        let mut app = App::new(1000, 100.0);
+        app.init_from_topology(&topology, &with_energy(0.0, 0, 0));
+        // 2 W in both idle intervals; 2.5 W and 4.5 W (across a counter wrap) at 4000 irqs/s
+        app.sample(&with_energy(1.0, 0, 2_000_000));
+        app.sample(&with_energy(2.0, 4000, 4_500_000));
+        app.sample(&with_energy(3.0, 4000, 6_500_000));
+        app.sample(&with_energy(4.0, 8000, 1_000_000));
+
+        assert_eq!(app.power_data[0].data.back(), Some(&(4.0, 4.5)));
+        let split = app.energy_by_load().unwrap();
+        assert_eq!((split.low_intervals, split.high_intervals), (2, 2));
+        assert_eq!((split.low_rate, split.high_rate), (0.0, 4000.0));
+        assert_eq!(split.zones, [("package-0".to_string(), 2.0, 3.5)]);
+    }
+
+    #[test]
+    fn test_power_split_uses_session_mean_rate() {
+        let topology = topology();
+        let with_energy = |timestamp: f64, controller: u64, energy_uj: u64| {
+            let mut snapshot = snapshot(timestamp, controller, 0);
+            snapshot.energy = vec![EnergyZone {
+                zone: "intel-rapl:0".to_string(),
+                name: "package-0".to_string(),
+                energy_uj,
+                max_energy_range_uj: None,
+            }];
+            snapshot
+        };
+
+        let mut app = App::new(1000, 100.0);
+        app.init_from_topology(&topology, &with_energy(0.0, 0, 0));
+        // Load ramps up: 100, 200, 300 and 400 irqs/s at 2, 2, 3 and 3 W
+        app.sample(&with_energy(1.0, 100, 2_000_000));
+        app.sample(&with_energy(2.0, 300, 4_000_000));
+        app.sample(&with_energy(3.0, 600, 7_000_000));
+        app.sample(&with_energy(4.0, 1000, 10_000_000));
+
+        let split = app.energy_by_load().unwrap();
+        assert_eq!((split.low_intervals, split.high_intervals), (2, 2));
+        assert_eq!((split.low_rate, split.high_rate), (150.0, 350.0));
+        assert_eq!(split.zones, [("package-0".to_string(), 2.0, 3.0)]);
+    }
+
+    #[test]
+    fn test_suspend_gap_is_not_plotted() {
+        let topology = topology();
+        let with_suspend = |timestamp: f64, controller: u64, suspended_s: f64| {
+            let mut snapshot = snapshot(timestamp, controller, 0);
+            snapshot.suspend = Some(SuspendState {
+                suspended_s,
+                wakeup_irq: Some("9".to_string()),
+                ..Default::default()
+            });
+            snapshot
+        };
+
+        let mut app = App::new(1000, 100.0);
+        app.init_from_topology(&topology, &with_suspend(0.0, 0, 5.0));
+        app.sample(&with_suspend(1.0, 100, 5.0));
+        // Suspended for 60s; resume traffic must not show up as a rate
+        app.sample(&with_suspend(2.0, 50_100, 65.0));
+        app.sample(&with_suspend(3.0, 50_200, 65.0));
+
+        assert_eq!(app.sample_count, 2);
+        assert_eq!(app.sources[0].data.len(), 2);
+        assert_eq!(app.sources[0].rate_max, 100.0);
+        assert_eq!(app.sources[0].total_delta, 200);
+        assert_eq!(app.suspend_gaps.len(), 1);
+        let (elapsed, gap) = &app.suspend_gaps[0];
+        assert_eq!(*elapsed, 2.0);
+        assert_eq!(gap.duration_s, 60.0);
+        assert_eq!(gap.wakeup_irq.as_deref(), Some("9"));
+    }
+
+    #[test]
+    fn test_rates_use_measured_interval() {
+        let topology = topology();
+        let mut app = App::new(1000, 100.0);
+        app.init_from_topology(&topology, &snapshot(0.0, 0, 0));
+
+        // A sample that arrives 1.25s after the previous one is not counted as 1s
+        app.sample(&snapshot(1.25, 500, 125));
+        assert_eq!(app.sources[0].latest_rate, 400.0);
+        assert_eq!(app.sources[1].latest_rate, 100.0);
+
+        app.sample(&snapshot(2.0, 800, 200));
+        assert_eq!(app.sources[0].latest_rate, 400.0);
+    }
 }
@@ -0,0 +1,248 @@
+//! Interrupts during the lifetime of a child process, like `time`.
+
+use std::ffi::OsString;
+use std::fmt::Write as _;
+use std::os::unix::process::ExitStatusExt;
+use std::process::{self, ExitStatus};
+
+use anyhow::{Context, Result};
+
+use crate::discovery::{self, InterruptSourceInfo};
+use crate::roots::FsRoots;
+use crate::sampler::{Backend, Snapshot};
+use crate::suspend::{self, SuspendGap};
+
+/// Interrupt totals between two snapshots.
+struct Report {
+    /// (source, interrupts) in topology order
+    sources: Vec<(InterruptSourceInfo, u64)>,
+    /// Time between the snapshots, excluding time suspended
+    elapsed_s: f64,
+    suspend: Option<SuspendGap>,
+}
+
+impl Report {
+    fn new(sources: &[InterruptSourceInfo], before: &Snapshot, after: &Snapshot) -> Self {
+        let suspend = match (&before.suspend, &after.suspend) {
+            (Some(before), Some(after)) => suspend::detect(before, after),
+            _ => None,
+        };
+        Self {
+            sources: sources
+                .iter()
+                .map(|source| {
+                    let count = |s: &Snapshot| s.total(&source.irq).unwrap_or(0);
+                    (source.clone(), count(after).saturating_sub(count(before)))
+                })
+                .collect(),
+            elapsed_s: after.timestamp - before.timestamp,
+            suspend,
+        }
+    }
+
+    fn rate(&self, count: u64) -> f64 {
+        if self.elapsed_s > 0.0 {
+            count as f64 / self.elapsed_s
+        } else {
+            0.0
+        }
+    }
+
+    fn render(&self, command: &str, status: &str) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "\n=== Interrupts during `{}` ===\n", command);
+        let _ = write!(out, "Elapsed: {:.2}s", self.elapsed_s);
+        if let Some(gap) = &self.suspend {
+            let _ = write!(out, " awake, {}", gap.describe());
+        }
+        let _ = writeln!(out, " | {}\n", status);
+
+        let _ = writeln!(
+            out,
+            "{:<40} {:>12} {:>12} {:>14}",
+            "Source", "Interrupts", "Avg Rate", "Type"
+        );
+        let _ = writeln!(out, "{}", "-".repeat(81));
+        for (source, count) in &self.sources {
+            let name = if source.is_controller {
+                source.name.clone()
+            } else {
+                format!("  \u{2514}\u{2500} {}", source.name)
+            };
+            let type_str = if source.is_controller {
+                "Controller"
+            } else {
+                &source.device_type
+            };
+            let _ = writeln!(
+                out,
+                "{:<40} {:>12} {:>10.1}/s {:>14}",
+                name,
+                count,
+                self.rate(*count),
+                type_str
+            );
+        }
+
+        let _ = writeln!(out, "\n=== Per Controller ===\n");
+        let _ = writeln!(
+            out,
+            "{:<24} {:>12} {:>12} {:>12} {:>12} {:>8}",
+            "Controller", "Controller", "HID", "Total", "Avg Rate", "Ratio"
+        );
+        let _ = writeln!(out, "{}", "-".repeat(85));
+        for (controller, ctrl_count) in self.sources.iter().filter(|(s, _)| s.is_controller) {
+            let hid: Vec<u64> = self
+                .sources
+                .iter()
+                .filter(|(s, _)| !s.is_controller && s.controller == controller.controller)
+                .map(|(_, count)| *count)
+                .collect();
+            let hid_count: u64 = hid.iter().sum();
+            let total = ctrl_count + hid_count;
+            let ratio = match hid_count {
+                _ if hid.is_empty() => "-".to_string(),
+                0 => "idle".to_string(),
+                hid_count => format!("{:.1}:1", *ctrl_count as f64 / hid_count as f64),
+            };
+            let _ = writeln!(
+                out,
+                "{:<24} {:>12} {:>12} {:>12} {:>10.1}/s {:>8}",
+                controller.controller,
+                ctrl_count,
+                hid_count,
+                total,
+                self.rate(total),
+                ratio
+            );
+        }
+        out
+    }
+}
+
+/// Describe how the child exited (e.g., "exit status 0", "killed by signal 9").
+fn describe_status(status: ExitStatus) -> String {
+    match (status.code(), status.signal()) {
+        (Some(code), _) => format!("exit status {}", code),
+        (None, Some(signal)) => format!("killed by signal {}", signal),
+        _ => "unknown exit status".to_string(),
+    }
+}
+
+/// Exit code that passes the child's status on, using the shell's 128 + signal convention.
+fn exit_code(status: ExitStatus) -> u8 {
+    match (status.code(), status.signal()) {
+        (Some(code), _) => code as u8,
+        (None, Some(signal)) => (128 + signal) as u8,
+        _ => 1,
+    }
+}
+
+/// Ignore the signals a terminal sends to the whole foreground group, returning the previous dispositions.
+fn ignore_terminal_signals() -> [libc::sighandler_t; 2] {
+    // SAFETY: SIG_IGN is a valid disposition
+    unsafe {
+        [
+            libc::signal(libc::SIGINT, libc::SIG_IGN),
+            libc::signal(libc::SIGQUIT, libc::SIG_IGN),
+        ]
+    }
+}
+
+fn restore_terminal_signals(previous: [libc::sighandler_t; 2]) {
+    // SAFETY: the dispositions were returned by signal()
+    unsafe {
+        libc::signal(libc::SIGINT, previous[0]);
+        libc::signal(libc::SIGQUIT, previous[1]);
+    }
+}
+
+/// Run a command, report the interrupts during its lifetime on stderr and
+/// return its exit code.
+pub fn run(command: &[OsString], backend: Backend, roots: &FsRoots) -> Result<u8> {
+    let (program, args) = command.split_first().context("no command given")?;
+    let topology = discovery::discover(roots)?;
+    let sources = topology.all_sources();
+    if sources.is_empty() {
+        anyhow::bail!("No I2C-related interrupt sources found.");
+    }
+
+    let mut sampler = backend.open(&topology, roots);
+    let before = sampler
+        .sample()?
+        .context("no interrupt snapshots available")?;
+
+    let mut child = process::Command::new(program)
+        .args(args)
+        .spawn()
+        .with_context(|| format!("failed to run {}", program.to_string_lossy()))?;
+    // Like time(1), let Ctrl-C stop the child but keep running to print the report
+    let previous = ignore_terminal_signals();
+    let status = child.wait();
+    restore_terminal_signals(previous);
+    let status = status.context("failed to wait for the command")?;
+
+    let after = sampler
+        .sample()?
+        .context("no interrupt snapshots available")?;
+    let command_line: Vec<_> = command.iter().map(|a| a.to_string_lossy()).collect();
+    eprint!(
+        "{}",
+        Report::new(&sources, &before, &after)
+            .render(&command_line.join(" "), &describe_status(status))
+    );
+
+    Ok(exit_code(status))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture;
+    use crate::suspend::SuspendState;
+
+    fn snapshot(timestamp: f64, controller: u64, hid: u64, suspended_s: f64) -> Snapshot {
+        let controller = [(0, controller / 2), (1, controller - controller / 2)];
+        let mut snapshot =
+            fixture::snapshot(timestamp, &[("21", &controller), ("203", &[(0, hid)])]);
+        snapshot.suspend = Some(SuspendState {
+            suspended_s,
+            ..Default::default()
+        });
+        snapshot
+    }
+
+    #[test]
+    fn test_report_totals_and_ratio() {
+        let before = snapshot(1.0, 1000, 10, 5.0);
+        let after = snapshot(5.0, 14600, 410, 5.0);
+        let report = Report::new(&fixture::touchpad_sources(), &before, &after);
+        assert_eq!(report.sources[0].1, 13600);
+        assert_eq!(report.sources[1].1, 400);
+        assert_eq!(report.rate(400), 100.0);
+        assert_eq!(report.suspend, None);
+
+        let text = report.render("evemu-play touchpad.events", "exit status 0");
+        assert!(text.contains("Elapsed: 4.00s | exit status 0"));
+        assert!(text.contains(" 34.0:1"));
+
+        // A suspend test: rates cover the awake time only
+        let after = snapshot(5.0, 14600, 410, 35.0);
+        let report = Report::new(&fixture::touchpad_sources(), &before, &after);
+        assert_eq!(report.suspend.as_ref().unwrap().duration_s, 30.0);
+        assert!(
+            report
+                .render("rtcwake -m freeze -s 30", "exit status 0")
+                .contains("Elapsed: 4.00s awake, suspended 30.0s")
+        );
+    }
+
+    #[test]
+    fn test_exit_code_passes_child_status_on() {
+        let status = |raw| ExitStatus::from_raw(raw);
+        assert_eq!(exit_code(status(0)), 0);
+        assert_eq!(exit_code(status(3 << 8)), 3);
+        assert_eq!(exit_code(status(libc::SIGKILL)), 137);
+        assert_eq!(describe_status(status(libc::SIGKILL)), "killed by signal 9");
+    }
+}